@@ -0,0 +1,609 @@
+use crate::conflict::{self, ConflictPolicy, CopyNameFormat, EntryDecision, ExistingEntry};
+use crate::file_ops;
+use crate::history::MergeOutcome;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Datelike, Local, TimeZone, Timelike};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use once_cell::sync::Lazy;
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
+use tracing::{info, warn};
+use xz2::read::XzDecoder;
+use zip::write::FileOptions;
+
+/// 解压时存放压缩包内容的临时目录名
+const CONTENT_DIR: &str = ".floatsort-content";
+
+/// 归档写入锁：并发重写同一个压缩包会丢失条目，因此归档操作依次执行
+static ARCHIVE_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+/// 支持的压缩包格式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArchiveFormat {
+    Zip,
+    Tar,
+    TarGz,
+    TarXz,
+}
+
+impl ArchiveFormat {
+    /// 根据文件名判断压缩包格式，返回格式和去掉扩展名后的名称
+    pub fn detect(file_name: &str) -> Option<(Self, &str)> {
+        const SUFFIXES: &[(&str, ArchiveFormat)] = &[
+            (".tar.gz", ArchiveFormat::TarGz),
+            (".tgz", ArchiveFormat::TarGz),
+            (".tar.xz", ArchiveFormat::TarXz),
+            (".txz", ArchiveFormat::TarXz),
+            (".tar", ArchiveFormat::Tar),
+            (".zip", ArchiveFormat::Zip),
+        ];
+
+        SUFFIXES.iter().find_map(|(suffix, format)| {
+            let split = file_name.len().checked_sub(suffix.len()).filter(|n| *n > 0)?;
+            let tail = file_name.get(split..)?;
+            tail.eq_ignore_ascii_case(suffix)
+                .then(|| (*format, &file_name[..split]))
+        })
+    }
+}
+
+/// 解压选项
+#[derive(Debug, Clone, Default)]
+pub struct ExtractOptions {
+    /// 在目标目录中创建以压缩包命名的子文件夹
+    pub create_subfolder: bool,
+    /// 只有一个顶层文件夹时直接解压其内容
+    pub flatten_single_root: bool,
+}
+
+/// 解压结果
+#[derive(Debug, Clone)]
+pub struct Extracted {
+    /// 解压结果的路径（子文件夹或目标目录）
+    pub path: String,
+    /// 已放到最终位置的顶层条目数
+    pub placed: usize,
+    /// 顶层条目总数
+    pub total: usize,
+}
+
+impl Extracted {
+    /// 是否所有条目都已放到最终位置（没有因冲突策略被跳过的）
+    pub fn is_complete(&self) -> bool {
+        self.placed == self.total
+    }
+}
+
+/// 解压压缩包到目标目录
+///
+/// 先解压到目标目录下的临时目录，全部成功后再按冲突策略移动到最终位置；
+/// 压缩包中含有绝对路径或 `..` 的条目时拒绝解压，且不会留下任何文件。
+/// 移动到最终位置时出错，已移动的条目会被撤回。
+pub fn extract(source: &Path, dest_dir: &Path, options: &ExtractOptions, policy: &ConflictPolicy) -> Result<Extracted> {
+    let file_name = source
+        .file_name()
+        .and_then(|n| n.to_str())
+        .context("无法获取压缩包文件名")?;
+    let (format, stem) = ArchiveFormat::detect(file_name)
+        .with_context(|| format!("不支持的压缩包格式: {}", file_name))?;
+
+    fs::create_dir_all(dest_dir)
+        .with_context(|| format!("创建目录失败: {:?}", dest_dir))?;
+
+    let staging = dest_dir.join(format!(".{}.extracting", file_name));
+    if staging.exists() {
+        // 上次解压中断遗留的临时目录
+        fs::remove_dir_all(&staging)
+            .with_context(|| format!("清理临时目录失败: {:?}", staging))?;
+    }
+
+    let content = staging.join(CONTENT_DIR);
+    let result = unpack(source, format, &content)
+        .and_then(|_| place(&staging, &content, stem, dest_dir, options, policy));
+
+    if let Err(e) = fs::remove_dir_all(&staging) {
+        warn!("清理临时目录失败: {:?} - {}", staging, e);
+    }
+
+    let extracted = result.with_context(|| format!("解压失败: {:?}", source))?;
+    if extracted.is_complete() {
+        info!("压缩包已解压: {:?} -> {}", source, extracted.path);
+    } else {
+        info!("压缩包已解压 {}/{} 项，其余已跳过: {:?} -> {}", extracted.placed, extracted.total, source, extracted.path);
+    }
+    Ok(extracted)
+}
+
+/// 解压全部条目到指定目录
+fn unpack(source: &Path, format: ArchiveFormat, target: &Path) -> Result<()> {
+    fs::create_dir_all(target)
+        .with_context(|| format!("创建目录失败: {:?}", target))?;
+    let file = File::open(source)
+        .with_context(|| format!("无法打开压缩包: {:?}", source))?;
+
+    match format {
+        ArchiveFormat::Zip => unpack_zip(file, target),
+        ArchiveFormat::Tar => unpack_tar(file, target),
+        ArchiveFormat::TarGz => unpack_tar(GzDecoder::new(file), target),
+        ArchiveFormat::TarXz => unpack_tar(XzDecoder::new(file), target),
+    }
+}
+
+fn unpack_zip(file: File, target: &Path) -> Result<()> {
+    let mut archive = zip::ZipArchive::new(file).context("无法读取 zip 压缩包")?;
+
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index)?;
+        let relative = safe_relative_path(entry.name())?;
+        if relative.as_os_str().is_empty() {
+            continue;
+        }
+
+        let out_path = target.join(&relative);
+        if entry.is_dir() {
+            fs::create_dir_all(&out_path)?;
+            continue;
+        }
+        if entry.unix_mode().is_some_and(|mode| mode & 0o170000 == 0o120000) {
+            warn!("跳过压缩包中的符号链接: {}", entry.name());
+            continue;
+        }
+
+        if let Some(parent) = out_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut writer = File::create(&out_path)
+            .with_context(|| format!("创建文件失败: {:?}", out_path))?;
+        io::copy(&mut entry, &mut writer)
+            .with_context(|| format!("解压文件失败: {}", entry.name()))?;
+    }
+
+    Ok(())
+}
+
+fn unpack_tar<R: Read>(reader: R, target: &Path) -> Result<()> {
+    let mut archive = tar::Archive::new(reader);
+
+    for entry in archive.entries().context("无法读取 tar 压缩包")? {
+        let mut entry = entry?;
+        let name = entry.path()?.to_string_lossy().to_string();
+        let relative = safe_relative_path(&name)?;
+        if relative.as_os_str().is_empty() {
+            continue;
+        }
+
+        let out_path = target.join(&relative);
+        let entry_type = entry.header().entry_type();
+        if entry_type.is_dir() {
+            fs::create_dir_all(&out_path)?;
+            continue;
+        }
+        if !entry_type.is_file() {
+            // 链接可能指向解压目录之外，一律跳过
+            warn!("跳过压缩包中的链接或特殊文件: {}", name);
+            continue;
+        }
+
+        if let Some(parent) = out_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        entry
+            .unpack(&out_path)
+            .with_context(|| format!("解压文件失败: {}", name))?;
+    }
+
+    Ok(())
+}
+
+/// 校验压缩包条目路径并转换为相对路径（拒绝绝对路径和 `..` 路径穿越）
+fn safe_relative_path(name: &str) -> Result<PathBuf> {
+    let normalized = name.replace('\\', "/");
+
+    // Windows 盘符（如 C:/），在其他平台上不会被识别为绝对路径
+    if normalized.as_bytes().get(1) == Some(&b':') {
+        anyhow::bail!("压缩包包含不安全的路径: {}", name);
+    }
+
+    let mut relative = PathBuf::new();
+    for component in Path::new(&normalized).components() {
+        match component {
+            Component::Normal(part) => relative.push(part),
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => {
+                anyhow::bail!("压缩包包含不安全的路径: {}", name);
+            }
+        }
+    }
+    Ok(relative)
+}
+
+/// 将解压出的内容移动到最终位置
+///
+/// 任一条目出错时撤回已移动的条目并返回错误。
+fn place(
+    staging: &Path,
+    content: &Path,
+    stem: &str,
+    dest_dir: &Path,
+    options: &ExtractOptions,
+    policy: &ConflictPolicy,
+) -> Result<Extracted> {
+    let mut root = content.to_path_buf();
+    if options.flatten_single_root {
+        let children = fs::read_dir(content)?.collect::<io::Result<Vec<_>>>()?;
+        if children.len() == 1 && children[0].file_type()?.is_dir() {
+            root = children[0].path();
+        }
+    }
+
+    let entries = if options.create_subfolder {
+        let named = staging.join(stem);
+        fs::rename(&root, &named)?;
+        vec![named]
+    } else {
+        fs::read_dir(&root)?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<io::Result<Vec<_>>>()?
+    };
+
+    let mut extracted = Extracted {
+        path: dest_dir.to_string_lossy().to_string(),
+        placed: 0,
+        total: entries.len(),
+    };
+    let mut moved = Vec::new();
+    for entry in &entries {
+        match place_entry(entry, dest_dir, policy, &mut moved) {
+            Ok((path, placed)) => {
+                if options.create_subfolder {
+                    extracted.path = path;
+                }
+                if placed {
+                    extracted.placed += 1;
+                }
+            }
+            Err(e) => {
+                roll_back(&moved);
+                return Err(e);
+            }
+        }
+    }
+    Ok(extracted)
+}
+
+/// 按冲突策略移动一个条目，返回目标路径和是否已完整放置
+///
+/// 新放到目标目录中的文件记录到 `moved`（目标路径，临时目录中的原路径），
+/// 出错时用于撤回；覆盖了已有文件的无法撤回，不记录。
+fn place_entry(source: &Path, dest_dir: &Path, policy: &ConflictPolicy, moved: &mut Vec<(PathBuf, PathBuf)>) -> Result<(String, bool)> {
+    let name = source.file_name().context("无法获取文件名")?;
+    let existing = dest_dir.join(name);
+    let existed = existing.symlink_metadata().is_ok();
+
+    let outcome = file_ops::move_file_checked(source, &dest_dir.to_string_lossy(), policy, None)?;
+    let target = PathBuf::from(&outcome.path);
+    for merged in &outcome.merged {
+        let origin = source.join(&merged.path);
+        match &merged.outcome {
+            MergeOutcome::Added => moved.push((target.join(&merged.path), origin)),
+            MergeOutcome::KeptBoth { name } => moved.push((target.join(&merged.path).with_file_name(name), origin)),
+            _ => {}
+        }
+    }
+    if outcome.merged.is_empty() && !(existed && target == existing) && source.symlink_metadata().is_err() {
+        moved.push((target, source.to_path_buf()));
+    }
+
+    // 被跳过或合并时有剩余的条目仍在临时目录中
+    Ok((outcome.path, source.symlink_metadata().is_err()))
+}
+
+/// 撤回已移动的条目（移回临时目录，随临时目录一起清理）
+fn roll_back(moved: &[(PathBuf, PathBuf)]) {
+    for (target, origin) in moved.iter().rev() {
+        let result = origin
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| fs::rename(target, origin));
+        match result {
+            Ok(()) => info!("已撤回解压的条目: {:?}", target),
+            Err(e) => warn!("撤回解压的条目失败: {:?} - {}", target, e),
+        }
+    }
+}
+
+/// 将文件添加到压缩包（zip 或 tar.gz），返回写入的条目名
+///
+/// 新压缩包先写入同目录下的临时文件，条目内容与原文件逐字节校验一致后
+/// 再替换原压缩包，因此中途失败不会损坏已有的压缩包。
+/// 已存在同名条目时按冲突策略处理，策略为跳过时返回 None。
+pub fn append_to_archive(source: &Path, archive_path: &Path, policy: &ConflictPolicy) -> Result<Option<String>> {
+    if source.is_dir() {
+        anyhow::bail!("暂不支持将文件夹添加到压缩包: {:?}", source);
+    }
+
+    let archive_name = archive_path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .context("无法获取压缩包文件名")?;
+    let format = match ArchiveFormat::detect(archive_name) {
+        Some((format @ (ArchiveFormat::Zip | ArchiveFormat::TarGz), _)) => format,
+        _ => anyhow::bail!("归档仅支持 .zip 和 .tar.gz 格式: {}", archive_name),
+    };
+    let entry_name = source
+        .file_name()
+        .and_then(|n| n.to_str())
+        .context("无法获取文件名")?;
+
+    let _guard = ARCHIVE_LOCK
+        .lock()
+        .map_err(|e| anyhow::anyhow!("无法获取归档锁: {}", e))?;
+
+    let parent = archive_path.parent().context("无法获取压缩包所在目录")?;
+    fs::create_dir_all(parent)
+        .with_context(|| format!("创建目录失败: {:?}", parent))?;
+    let temp = parent.join(format!(".{}.writing", archive_name));
+
+    let written = match format {
+        ArchiveFormat::Zip => write_zip(archive_path, &temp, source, entry_name, policy),
+        _ => write_tar_gz(archive_path, &temp, source, entry_name, policy),
+    };
+    let result = written.and_then(|entry| {
+        if let Some(entry) = &entry {
+            verify_entry(&temp, format, entry, source)?;
+        }
+        Ok(entry)
+    });
+
+    match result {
+        Ok(Some(entry)) => {
+            fs::rename(&temp, archive_path)
+                .with_context(|| format!("替换压缩包失败: {:?}", archive_path))?;
+            info!("文件已归档: {:?} -> {:?} ({})", source, archive_path, entry);
+            Ok(Some(entry))
+        }
+        Ok(None) => {
+            let _ = fs::remove_file(&temp);
+            info!("压缩包中已存在同名条目，跳过: {:?} ({})", archive_path, entry_name);
+            Ok(None)
+        }
+        Err(e) => {
+            let _ = fs::remove_file(&temp);
+            Err(e.context(format!("归档失败: {:?} -> {:?}", source, archive_path)))
+        }
+    }
+}
+
+/// 复制已有的 zip 条目并追加新文件
+fn write_zip(archive_path: &Path, temp: &Path, source: &Path, entry_name: &str, policy: &ConflictPolicy) -> Result<Option<String>> {
+    let mut writer = zip::ZipWriter::new(File::create(temp)?);
+    let mut names = HashSet::new();
+
+    if archive_path.exists() {
+        let mut existing = zip::ZipArchive::new(File::open(archive_path)?)
+            .context("无法读取已有的 zip 压缩包")?;
+        for index in 0..existing.len() {
+            let entry = existing.by_index(index)?;
+            let name = entry.name().to_string();
+            if name == entry_name {
+                let info = ExistingEntry { size: entry.size(), modified: zip_timestamp(entry.last_modified()) };
+                match conflict::entry_decision(policy.file_strategy(), source, &info, archive_path)? {
+                    EntryDecision::Skip => return Ok(None),
+                    EntryDecision::Replace => continue,
+                    EntryDecision::KeepBoth => {}
+                }
+            }
+            writer.raw_copy_file(entry)?;
+            names.insert(name);
+        }
+    }
+
+    let name = unique_entry_name(entry_name, &names, &policy.copy_name);
+    writer.start_file(name.as_str(), zip_options(source)?)?;
+    io::copy(&mut File::open(source)?, &mut writer)?;
+    writer.finish()?.sync_all()?;
+    Ok(Some(name))
+}
+
+/// zip 条目选项（保留修改时间和权限）
+fn zip_options(source: &Path) -> Result<FileOptions> {
+    let metadata = fs::metadata(source)?;
+    let mut options = FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+
+    if let Ok(modified) = metadata.modified() {
+        let modified: DateTime<Local> = modified.into();
+        if let Ok(time) = zip::DateTime::from_date_and_time(
+            modified.year() as u16,
+            modified.month() as u8,
+            modified.day() as u8,
+            modified.hour() as u8,
+            modified.minute() as u8,
+            modified.second() as u8,
+        ) {
+            options = options.last_modified_time(time);
+        }
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        options = options.unix_permissions(metadata.permissions().mode());
+    }
+
+    Ok(options)
+}
+
+/// zip 条目的修改时间（按本地时间保存）转为 Unix 秒
+fn zip_timestamp(time: zip::DateTime) -> Option<i64> {
+    Local
+        .with_ymd_and_hms(
+            time.year() as i32,
+            time.month() as u32,
+            time.day() as u32,
+            time.hour() as u32,
+            time.minute() as u32,
+            time.second() as u32,
+        )
+        .single()
+        .map(|t| t.timestamp())
+}
+
+/// 重新压缩已有的 tar.gz 条目并追加新文件（gzip 流无法原地追加）
+fn write_tar_gz(archive_path: &Path, temp: &Path, source: &Path, entry_name: &str, policy: &ConflictPolicy) -> Result<Option<String>> {
+    let mut builder = tar::Builder::new(GzEncoder::new(File::create(temp)?, Compression::default()));
+    let mut names = HashSet::new();
+
+    if archive_path.exists() {
+        let mut existing = tar::Archive::new(GzDecoder::new(File::open(archive_path)?));
+        for entry in existing.entries().context("无法读取已有的 tar.gz 压缩包")? {
+            let mut entry = entry?;
+            let path = entry.path()?.into_owned();
+            let name = path.to_string_lossy().to_string();
+            if name == entry_name {
+                let header = entry.header();
+                let info = ExistingEntry { size: header.size()?, modified: header.mtime().ok().map(|t| t as i64) };
+                match conflict::entry_decision(policy.file_strategy(), source, &info, archive_path)? {
+                    EntryDecision::Skip => return Ok(None),
+                    EntryDecision::Replace => continue,
+                    EntryDecision::KeepBoth => {}
+                }
+            }
+            let mut header = entry.header().clone();
+            builder.append_data(&mut header, &path, &mut entry)?;
+            names.insert(name);
+        }
+    }
+
+    let name = unique_entry_name(entry_name, &names, &policy.copy_name);
+    builder.append_path_with_name(source, &name)?;
+    builder.into_inner()?.finish()?.sync_all()?;
+    Ok(Some(name))
+}
+
+/// 为重名的条目生成副本名称
+fn unique_entry_name(name: &str, existing: &HashSet<String>, format: &CopyNameFormat) -> String {
+    if !existing.contains(name) {
+        return name.to_string();
+    }
+
+    let path = Path::new(name);
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or(name);
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+    format.next_free(stem, extension, existing.iter().map(String::as_str))
+}
+
+/// 校验压缩包中的条目与原文件内容一致
+fn verify_entry(archive_path: &Path, format: ArchiveFormat, entry_name: &str, source: &Path) -> Result<()> {
+    let verified = match format {
+        ArchiveFormat::Zip => {
+            let mut archive = zip::ZipArchive::new(File::open(archive_path)?)?;
+            let mut entry = archive.by_name(entry_name)?;
+            same_content(&mut entry, &mut File::open(source)?)?
+        }
+        _ => {
+            let mut archive = tar::Archive::new(GzDecoder::new(File::open(archive_path)?));
+            let mut verified = false;
+            for entry in archive.entries()? {
+                let mut entry = entry?;
+                if entry.path()?.to_string_lossy() == entry_name {
+                    verified = same_content(&mut entry, &mut File::open(source)?)?;
+                }
+            }
+            verified
+        }
+    };
+
+    if !verified {
+        anyhow::bail!("压缩包条目校验失败: {}", entry_name);
+    }
+    Ok(())
+}
+
+/// 逐块比较两个数据流的内容
+fn same_content<A: Read, B: Read>(a: &mut A, b: &mut B) -> Result<bool> {
+    let mut buf_a = vec![0u8; 64 * 1024];
+    let mut buf_b = vec![0u8; 64 * 1024];
+
+    loop {
+        let len_a = read_full(a, &mut buf_a)?;
+        let len_b = read_full(b, &mut buf_b)?;
+        if len_a != len_b || buf_a[..len_a] != buf_b[..len_b] {
+            return Ok(false);
+        }
+        if len_a == 0 {
+            return Ok(true);
+        }
+    }
+}
+
+/// 尽量读满缓冲区，返回读取的字节数（小于缓冲区长度表示已到末尾）
+fn read_full<R: Read>(reader: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_and_safe_paths() {
+        assert_eq!(ArchiveFormat::detect("report.TAR.GZ"), Some((ArchiveFormat::TarGz, "report")));
+        assert_eq!(ArchiveFormat::detect("bundle.zip"), Some((ArchiveFormat::Zip, "bundle")));
+        assert_eq!(ArchiveFormat::detect(".zip"), None);
+        assert_eq!(ArchiveFormat::detect("notes.txt"), None);
+
+        assert_eq!(safe_relative_path("./a/b.txt").unwrap(), PathBuf::from("a/b.txt"));
+        assert!(safe_relative_path("../evil.sh").is_err());
+        assert!(safe_relative_path("a/../../evil.sh").is_err());
+        assert!(safe_relative_path("/etc/passwd").is_err());
+        assert!(safe_relative_path("C:\\Windows\\evil.dll").is_err());
+    }
+
+    #[test]
+    fn test_extract_skipped_and_rolled_back() {
+        use crate::models::ConflictStrategy;
+
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let dest = dir.join("out");
+        fs::create_dir_all(&dest).unwrap();
+        fs::write(dest.join("a.txt"), "old").unwrap();
+
+        let source = dir.join("bundle.tar");
+        let mut builder = tar::Builder::new(File::create(&source).unwrap());
+        for name in ["a.txt", "b.txt", "c.txt"] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(3);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, name, &b"new"[..]).unwrap();
+        }
+        builder.finish().unwrap();
+        drop(builder);
+
+        // 询问策略遇到冲突时出错，已放置的条目被撤回
+        let options = ExtractOptions::default();
+        assert!(extract(&source, &dest, &options, &ConflictStrategy::Ask.into()).is_err());
+        assert!(!dest.join("b.txt").exists() && !dest.join("c.txt").exists());
+        assert_eq!(fs::read_to_string(dest.join("a.txt")).unwrap(), "old");
+
+        let extracted = extract(&source, &dest, &options, &ConflictStrategy::Skip.into()).unwrap();
+        assert_eq!((extracted.placed, extracted.total), (2, 3));
+        assert!(!extracted.is_complete());
+        assert_eq!(fs::read_to_string(dest.join("b.txt")).unwrap(), "new");
+    }
+}
@@ -1,9 +1,14 @@
-use crate::models::Rule;
+use crate::models::{Rule, RuleSet};
+use crate::counters::SequenceCounter;
 use crate::i18n;
+use crate::path_vars;
+use crate::rule_packs::InstalledPack;
+use crate::transfer::PreserveMetadata;
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use tracing::{info, warn};
 
 /// 触发模式
@@ -72,6 +77,10 @@ pub struct WatchFolder {
     /// 关联的规则 ID 列表
     pub rule_ids: Vec<String>,
     
+    /// 关联的规则集 ID 列表
+    #[serde(default)]
+    pub rule_set_ids: Vec<String>,
+    
     /// 文件处理模式（默认为手动）- 兼容旧版本
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub processing_mode: Option<ProcessingMode>,
@@ -171,6 +180,26 @@ pub struct AppConfig {
     /// 整理规则列表（全局规则库）
     pub rules: Vec<Rule>,
     
+    /// 规则集列表
+    #[serde(default)]
+    pub rule_sets: Vec<RuleSet>,
+    
+    /// 已安装的内置规则包
+    #[serde(default)]
+    pub installed_packs: Vec<InstalledPack>,
+    
+    /// 序号计数器定义（用于 {seq:名称} 占位符）
+    #[serde(default)]
+    pub counters: Vec<SequenceCounter>,
+    
+    /// 用户定义的路径变量（在目标路径和监控路径中以 {var:名称} 引用）
+    #[serde(default)]
+    pub path_variables: BTreeMap<String, String>,
+    
+    /// 外部规则文件路径（TOML/YAML），设置后会监控并热加载
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rules_file: Option<String>,
+    
     /// 是否显示通知
     pub show_notifications: bool,
     
@@ -213,6 +242,22 @@ pub struct AppConfig {
     #[serde(default = "default_log_retention_days")]
     pub log_retention_days: i32,
     
+    /// 删除动作是否先将文件移入隔离区（关闭时直接删除）
+    #[serde(default = "default_use_quarantine")]
+    pub use_quarantine: bool,
+    
+    /// 隔离区保留天数 - 超过此天数的隔离文件会被彻底删除，-1表示永久保留
+    #[serde(default = "default_quarantine_retention_days")]
+    pub quarantine_retention_days: i32,
+    
+    /// 复制和跨分区移动时保留的元数据（修改时间、权限、扩展属性）
+    #[serde(default)]
+    pub preserve_metadata: PreserveMetadata,
+    
+    /// 副本文件名格式（如 "{name} ({n}){ext}"），未设置时使用当前语言的默认格式
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub copy_name_format: Option<String>,
+    
     /// 窗口X位置
     #[serde(skip_serializing_if = "Option::is_none")]
     pub window_x: Option<i32>,
@@ -273,6 +318,14 @@ fn default_log_retention_days() -> i32 {
     30  // 默认保留30天
 }
 
+fn default_use_quarantine() -> bool {
+    true
+}
+
+fn default_quarantine_retention_days() -> i32 {
+    30  // 默认保留30天
+}
+
 fn default_window_height() -> u32 {
     750
 }
@@ -301,11 +354,18 @@ impl Default for AppConfig {
                             ],
                         },
                     ],
-                    action: crate::models::RuleAction::MoveTo {
+                    actions: vec![crate::models::RuleAction::MoveTo {
                         destination: "Pictures".to_string(),
-                    },
+                    }],
                     priority: 1,
                     conflict_strategy: crate::models::ConflictStrategy::Skip,
+                    continue_matching: false,
+                    copy_name_format: None,
+                    merge_file_strategy: Default::default(),
+                    active_from: None,
+                    active_until: None,
+                    active_windows: vec![],
+                    action: None,
                 },
                 Rule {
                     id: "rule_documents".to_string(),
@@ -323,13 +383,25 @@ impl Default for AppConfig {
                             ],
                         },
                     ],
-                    action: crate::models::RuleAction::MoveTo {
+                    actions: vec![crate::models::RuleAction::MoveTo {
                         destination: "Documents".to_string(),
-                    },
+                    }],
                     priority: 2,
                     conflict_strategy: crate::models::ConflictStrategy::Skip,
+                    continue_matching: false,
+                    copy_name_format: None,
+                    merge_file_strategy: Default::default(),
+                    active_from: None,
+                    active_until: None,
+                    active_windows: vec![],
+                    action: None,
                 },
             ],
+            rule_sets: vec![],
+            installed_packs: vec![],
+            counters: vec![],
+            path_variables: BTreeMap::new(),
+            rules_file: None,
             show_notifications: true,
             log_level: "info".to_string(),
             batch_threshold: 1,
@@ -341,6 +413,10 @@ impl Default for AppConfig {
             file_stability_delay: default_stability_delay(),
             file_stability_checks: default_stability_checks(),
             log_retention_days: default_log_retention_days(),
+            use_quarantine: default_use_quarantine(),
+            quarantine_retention_days: default_quarantine_retention_days(),
+            preserve_metadata: PreserveMetadata::default(),
+            copy_name_format: None,
             window_x: None,
             window_y: None,
             is_collapsed: None,
@@ -352,6 +428,22 @@ impl Default for AppConfig {
 }
 
 impl AppConfig {
+    /// 展开路径中的变量（~、环境变量、XDG 目录和用户变量）
+    pub fn expand_path(&self, path: &str) -> Result<String> {
+        path_vars::expand(path, &self.path_variables)
+    }
+    
+    /// 获取监控文件夹的实际路径（变量无法展开时使用原始路径）
+    pub fn folder_path(&self, folder: &WatchFolder) -> PathBuf {
+        match self.expand_path(&folder.path) {
+            Ok(path) => PathBuf::from(path),
+            Err(e) => {
+                warn!("文件夹 '{}' 的路径无法展开: {}", folder.name, e);
+                PathBuf::from(&folder.path)
+            }
+        }
+    }
+    
     /// 从文件加载配置并自动迁移
     pub fn load_from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
         let path = path.as_ref();
@@ -380,6 +472,21 @@ impl AppConfig {
         
         if migrated {
             info!("已迁移 processing_mode 到新的 trigger_mode");
+        }
+        
+        // 迁移规则的单一 action 到 actions 流水线
+        let mut actions_migrated = false;
+        for rule in config.rules.iter_mut() {
+            if rule.migrate_action() {
+                actions_migrated = true;
+            }
+        }
+        
+        if actions_migrated {
+            info!("已迁移规则的 action 到新的 actions 流水线");
+        }
+        
+        if migrated || actions_migrated {
             // 保存迁移后的配置
             config.save_to_file(path)?;
         }
@@ -407,6 +514,7 @@ impl AppConfig {
                     name: folder_name,
                     enabled: old_config.auto_start.unwrap_or(false),
                     rule_ids: old_config.rules.iter().map(|r| r.id.clone()).collect(),
+                    rule_set_ids: vec![],
                     processing_mode: Some(ProcessingMode::Manual),
                     trigger_mode: TriggerMode::Manual,
                     schedule_type: None,
@@ -422,6 +530,11 @@ impl AppConfig {
             version: 2,
             folders,
             rules: old_config.rules,
+            rule_sets: old_config.rule_sets,
+            installed_packs: old_config.installed_packs,
+            counters: old_config.counters,
+            path_variables: old_config.path_variables,
+            rules_file: old_config.rules_file,
             show_notifications: old_config.show_notifications,
             log_level: old_config.log_level,
             batch_threshold: 1,
@@ -433,6 +546,10 @@ impl AppConfig {
             file_stability_delay: default_stability_delay(),
             file_stability_checks: default_stability_checks(),
             log_retention_days: default_log_retention_days(),
+            use_quarantine: default_use_quarantine(),
+            quarantine_retention_days: default_quarantine_retention_days(),
+            preserve_metadata: PreserveMetadata::default(),
+            copy_name_format: None,
             window_x: None,
             window_y: None,
             is_collapsed: None,
@@ -442,6 +559,73 @@ impl AppConfig {
         })
     }
 
+    /// 获取全局生效的规则（不属于任何规则集的规则，以及未关联到文件夹的已启用规则集中的规则）
+    ///
+    /// 关联到文件夹的规则集只在这些文件夹中生效。
+    pub fn active_rules(&self) -> Vec<Rule> {
+        let ungrouped: Vec<String> = self.rules.iter()
+            .filter(|r| !self.rule_sets.iter().any(|s| s.rule_ids.contains(&r.id)))
+            .map(|r| r.id.clone())
+            .collect();
+        let sets: Vec<&RuleSet> = self.rule_sets.iter()
+            .filter(|s| s.enabled && !self.folders.iter().any(|f| f.rule_set_ids.contains(&s.id)))
+            .collect();
+        self.resolve_rules(&ungrouped, &sets)
+    }
+    
+    /// 获取文件夹生效的规则（直接关联的规则，以及关联的已启用规则集中的规则）
+    ///
+    /// 文件夹没有关联任何规则或规则集时使用全局规则。
+    pub fn rules_for_folder(&self, folder: &WatchFolder) -> Vec<Rule> {
+        if folder.rule_ids.is_empty() && folder.rule_set_ids.is_empty() {
+            return self.active_rules();
+        }
+        let sets: Vec<&RuleSet> = self.rule_sets.iter()
+            .filter(|s| s.enabled && folder.rule_set_ids.contains(&s.id))
+            .collect();
+        self.resolve_rules(&folder.rule_ids, &sets)
+    }
+    
+    /// 解析规则的有效评估顺序
+    ///
+    /// 排序键依次为：规则集优先级、规则集在配置中的位置、规则优先级、规则在列表中的位置。
+    /// 直接指定的规则视为优先级为 0 且排在同优先级规则集之前的隐式规则集；
+    /// 同一规则出现在多处时只保留最靠前的一次。返回副本的 priority 被改写为有效顺序，
+    /// 规则引擎按 priority 排序时即得到同样的结果。
+    fn resolve_rules(&self, direct_ids: &[String], sets: &[&RuleSet]) -> Vec<Rule> {
+        // (规则集优先级, 规则集位置, 规则优先级, 规则位置)
+        type OrderKey = (i32, usize, i32, usize);
+        let mut keyed: Vec<(OrderKey, &Rule)> = Vec::new();
+        
+        let mut collect = |set_priority: i32, set_index: usize, rule_ids: &[String]| {
+            for (position, id) in rule_ids.iter().enumerate() {
+                if let Some(rule) = self.rules.iter().find(|r| &r.id == id) {
+                    keyed.push(((set_priority, set_index, rule.priority, position), rule));
+                }
+            }
+        };
+        
+        collect(0, 0, direct_ids);
+        for set in sets {
+            if let Some(index) = self.rule_sets.iter().position(|s| s.id == set.id) {
+                collect(set.priority, index + 1, &set.rule_ids);
+            }
+        }
+        
+        keyed.sort_by_key(|(key, _)| *key);
+        
+        let mut seen = std::collections::HashSet::new();
+        keyed.into_iter()
+            .filter(|(_, rule)| seen.insert(rule.id.as_str()))
+            .enumerate()
+            .map(|(order, (_, rule))| {
+                let mut rule = rule.clone();
+                rule.priority = order as i32;
+                rule
+            })
+            .collect()
+    }
+
     /// 加载配置或使用默认值
     pub fn load_or_default<P: AsRef<Path>>(path: P) -> Result<Self> {
         let path = path.as_ref();
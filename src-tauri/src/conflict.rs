@@ -0,0 +1,593 @@
+use crate::i18n;
+use crate::models::{ConflictStrategy, Rule, RuleAction};
+use crate::transfer;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local, Utc};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::SystemTime;
+use tracing::{info, warn};
+
+/// 压缩包条目修改时间的最大允许误差（zip 只精确到 2 秒）
+const ENTRY_TIME_TOLERANCE_SECS: i64 = 2;
+
+/// 同一毫秒内生成多个 ID 时使用的序号
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// 全局副本名称格式（未设置时使用当前语言的默认格式）
+static COPY_NAME_FORMAT: Lazy<RwLock<Option<String>>> = Lazy::new(|| RwLock::new(None));
+
+/// 设置全局副本名称格式
+pub fn set_copy_name_format(format: Option<String>) {
+    if let Ok(mut current) = COPY_NAME_FORMAT.write() {
+        *current = format;
+    }
+}
+
+/// 副本文件名格式
+///
+/// `{name}` 为主文件名，`{n}` 为副本序号（从 1 开始），`{ext}` 为带点的扩展名。
+/// 格式中没有 `{ext}` 时扩展名加在末尾，例如 `{name}-{n}` 生成 report-2.pdf。
+#[derive(Debug, Clone, PartialEq)]
+pub struct CopyNameFormat {
+    pattern: String,
+    /// 第一个副本单独使用的格式（不含序号，例如 "{name} (副本){ext}"）
+    first: Option<String>,
+}
+
+impl CopyNameFormat {
+    /// 检查并创建格式（必须包含 `{name}` 和一个 `{n}`，不能包含路径分隔符）
+    pub fn parse(pattern: &str) -> Result<Self> {
+        if !pattern.contains("{name}") || pattern.matches("{n}").count() != 1 {
+            anyhow::bail!("副本名称格式必须包含 {{name}} 和一个 {{n}}: {}", pattern);
+        }
+        if pattern.contains(['/', '\\']) {
+            anyhow::bail!("副本名称格式不能包含路径分隔符: {}", pattern);
+        }
+        Ok(Self { pattern: pattern.to_string(), first: None })
+    }
+
+    /// 各语言的默认格式（中文保持 file (副本).txt、file (副本 2).txt 的命名）
+    pub fn localized(language: &str) -> Self {
+        let (pattern, first) = match language {
+            "en-US" => ("{name} ({n}){ext}", None),
+            "ja-JP" => ("{name} - コピー ({n}){ext}", None),
+            _ => ("{name} (副本 {n}){ext}", Some("{name} (副本){ext}")),
+        };
+        Self { pattern: pattern.to_string(), first: first.map(String::from) }
+    }
+
+    /// 实际使用的格式：规则中的设置优先，其次是全局设置，最后是当前语言的默认格式
+    ///
+    /// 无效的格式会被忽略。
+    pub fn effective(rule_format: Option<&str>) -> Self {
+        let global = COPY_NAME_FORMAT.read().ok().and_then(|f| f.clone());
+        for pattern in [rule_format, global.as_deref()].into_iter().flatten() {
+            match Self::parse(pattern) {
+                Ok(format) => return format,
+                Err(e) => warn!("{}，使用默认格式", e),
+            }
+        }
+        Self::localized(&i18n::get_language())
+    }
+
+    /// 第 n 个副本的文件名（`extension` 不带点，没有扩展名时为空字符串）
+    pub fn name(&self, stem: &str, extension: &str, n: usize) -> String {
+        if let (1, Some(first)) = (n, &self.first) {
+            return fill(first, stem, extension);
+        }
+        let (prefix, suffix) = self.parts(stem, extension);
+        format!("{}{}{}", prefix, n, suffix)
+    }
+
+    /// 第一个未被占用的副本文件名
+    ///
+    /// 只遍历一次 `taken`（已存在的名称），从中找出已使用的序号，
+    /// 副本很多的目录中也不需要逐个尝试。
+    pub fn next_free<'a>(&self, stem: &str, extension: &str, taken: impl IntoIterator<Item = &'a str>) -> String {
+        let (prefix, suffix) = self.parts(stem, extension);
+        let first = self.first.as_ref().map(|first| fill(first, stem, extension));
+        let used: HashSet<usize> = taken
+            .into_iter()
+            .filter_map(|name| {
+                if first.as_deref() == Some(name) {
+                    return Some(1);
+                }
+                let n = name.strip_prefix(&prefix)?.strip_suffix(&suffix)?;
+                if n.is_empty() || !n.bytes().all(|b| b.is_ascii_digit()) {
+                    return None;
+                }
+                n.parse().ok()
+            })
+            .collect();
+        let n = (1..=used.len() + 1).find(|n| !used.contains(n)).unwrap_or(used.len() + 1);
+        self.name(stem, extension, n)
+    }
+
+    /// 序号之前和之后的部分
+    fn parts(&self, stem: &str, extension: &str) -> (String, String) {
+        let (prefix, suffix) = self.pattern.split_once("{n}").unwrap_or((&self.pattern, ""));
+        let suffix = if self.pattern.contains("{ext}") { suffix.to_string() } else { format!("{}{{ext}}", suffix) };
+        (fill(prefix, stem, extension), fill(&suffix, stem, extension))
+    }
+}
+
+/// 填入主文件名和扩展名（文件名本身包含 `{ext}` 等字样时不会被再次替换）
+fn fill(template: &str, stem: &str, extension: &str) -> String {
+    let extension = if extension.is_empty() { String::new() } else { format!(".{}", extension) };
+    template
+        .split("{name}")
+        .map(|part| part.replace("{ext}", &extension))
+        .collect::<Vec<_>>()
+        .join(stem)
+}
+
+/// 目标已存在时的处理方式：冲突策略和需要保留两者时使用的副本名称格式
+#[derive(Debug, Clone)]
+pub struct ConflictPolicy {
+    pub strategy: ConflictStrategy,
+    pub copy_name: CopyNameFormat,
+    /// 合并文件夹时同名文件的处理策略
+    pub merge_files: ConflictStrategy,
+}
+
+impl ConflictPolicy {
+    /// `copy_name_format` 为规则中的副本名称格式（未设置时使用全局设置）
+    pub fn new(strategy: ConflictStrategy, copy_name_format: Option<&str>) -> Self {
+        Self {
+            strategy,
+            copy_name: CopyNameFormat::effective(copy_name_format),
+            merge_files: ConflictStrategy::Skip,
+        }
+    }
+
+    /// 设置合并文件夹时同名文件的处理策略（询问和合并无法用于单个文件，按跳过处理）
+    pub fn with_merge_strategy(mut self, strategy: ConflictStrategy) -> Self {
+        self.merge_files = match strategy {
+            ConflictStrategy::Ask | ConflictStrategy::Merge => {
+                warn!("合并文件夹时同名文件不能使用 {:?} 策略，改为跳过", strategy);
+                ConflictStrategy::Skip
+            }
+            other => other,
+        };
+        self
+    }
+
+    /// 单个文件使用的策略（策略为合并时使用同名文件的策略）
+    pub fn file_strategy(&self) -> &ConflictStrategy {
+        match self.strategy {
+            ConflictStrategy::Merge => &self.merge_files,
+            ref other => other,
+        }
+    }
+
+    /// 合并文件夹时处理其中同名文件的方式
+    pub fn for_merged_files(&self) -> Self {
+        Self {
+            strategy: self.merge_files.clone(),
+            ..self.clone()
+        }
+    }
+}
+
+impl From<ConflictStrategy> for ConflictPolicy {
+    fn from(strategy: ConflictStrategy) -> Self {
+        Self::new(strategy, None)
+    }
+}
+
+/// 目标已存在且策略为询问时返回的错误，流水线据此将文件放入待处理队列
+#[derive(Debug)]
+pub struct ConflictPending {
+    pub target: PathBuf,
+}
+
+impl fmt::Display for ConflictPending {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "目标已存在，等待选择处理方式: {:?}", self.target)
+    }
+}
+
+impl std::error::Error for ConflictPending {}
+
+/// 从错误链中查找等待处理的冲突
+pub fn pending_target(error: &anyhow::Error) -> Option<&Path> {
+    error
+        .chain()
+        .find_map(|e| e.downcast_ref::<ConflictPending>())
+        .map(|p| p.target.as_path())
+}
+
+/// 等待用户处理的冲突
+#[derive(Debug, Clone, Serialize)]
+pub struct PendingConflict {
+    pub id: String,
+    /// 批次 ID（同一批次中可以记住选择）
+    pub batch: Option<String>,
+    pub rule_name: String,
+    /// 规则中的副本名称格式
+    #[serde(skip)]
+    pub copy_name_format: Option<String>,
+    /// 规则中合并文件夹时同名文件的处理策略
+    #[serde(skip)]
+    pub merge_file_strategy: ConflictStrategy,
+    /// 遇到冲突的动作，处理时按所选策略重新执行
+    pub action: RuleAction,
+    #[serde(skip)]
+    pub regex_captures: Vec<String>,
+    pub source: String,
+    /// 已存在的目标
+    pub target: String,
+    pub detected_at: DateTime<Utc>,
+}
+
+impl PendingConflict {
+    pub fn new(rule_name: &str, action: &RuleAction, regex_captures: &[String], source: &str, target: &Path, batch: Option<String>) -> Self {
+        Self {
+            id: format!("{}-{}", Local::now().format("%Y%m%d%H%M%S%3f"), NEXT_ID.fetch_add(1, Ordering::Relaxed)),
+            batch,
+            rule_name: rule_name.to_string(),
+            copy_name_format: None,
+            merge_file_strategy: ConflictStrategy::Skip,
+            action: action.clone(),
+            regex_captures: regex_captures.to_vec(),
+            source: source.to_string(),
+            target: target.to_string_lossy().to_string(),
+            detected_at: Utc::now(),
+        }
+    }
+
+    /// 保存规则中与冲突处理相关的设置（处理冲突时继续使用）
+    pub fn with_rule_settings(mut self, rule: &Rule) -> Self {
+        self.copy_name_format = rule.copy_name_format.clone();
+        self.merge_file_strategy = rule.merge_file_strategy.clone();
+        self
+    }
+
+    /// 按所选策略处理时使用的冲突处理方式
+    pub fn policy(&self, strategy: &ConflictStrategy) -> ConflictPolicy {
+        ConflictPolicy::new(strategy.clone(), self.copy_name_format.as_deref())
+            .with_merge_strategy(self.merge_file_strategy.clone())
+    }
+}
+
+/// 处理一个待处理冲突的结果
+#[derive(Debug, Clone, Serialize)]
+pub struct ConflictResolution {
+    pub id: String,
+    pub source: String,
+    pub output: Option<String>,
+    pub error: Option<String>,
+}
+
+/// 发送冲突事件的回调
+pub type ConflictEmitter = Arc<dyn Fn(&PendingConflict) + Send + Sync>;
+
+/// 等待用户处理的冲突队列（前端事件名为 conflict-pending）
+#[derive(Default)]
+pub struct ConflictQueue {
+    pending: Mutex<Vec<PendingConflict>>,
+    /// 各批次记住的选择
+    remembered: Mutex<HashMap<String, ConflictStrategy>>,
+    emitter: Mutex<Option<ConflictEmitter>>,
+}
+
+impl fmt::Debug for ConflictQueue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pending = self.pending.lock().map(|p| p.len()).unwrap_or(0);
+        f.debug_struct("ConflictQueue").field("pending", &pending).finish()
+    }
+}
+
+impl ConflictQueue {
+    /// 设置冲突事件的发送方式
+    pub fn set_emitter(&self, emitter: ConflictEmitter) {
+        if let Ok(mut guard) = self.emitter.lock() {
+            *guard = Some(emitter);
+        }
+    }
+
+    /// 放入待处理队列并通知前端
+    pub fn park(&self, conflict: PendingConflict) {
+        info!("冲突等待处理: {} -> {}", conflict.source, conflict.target);
+        if let Some(emitter) = self.emitter.lock().ok().and_then(|e| e.clone()) {
+            emitter(&conflict);
+        }
+        if let Ok(mut pending) = self.pending.lock() {
+            // 同一文件再次遇到冲突时替换之前的记录
+            pending.retain(|c| c.source != conflict.source);
+            pending.push(conflict);
+        }
+    }
+
+    /// 列出待处理的冲突（按发生顺序）
+    pub fn list(&self) -> Vec<PendingConflict> {
+        self.pending.lock().map(|p| p.clone()).unwrap_or_default()
+    }
+
+    /// 批次中记住的选择
+    pub fn remembered(&self, batch: Option<&str>) -> Option<ConflictStrategy> {
+        let batch = batch?;
+        self.remembered.lock().ok()?.get(batch).cloned()
+    }
+
+    /// 取出要按所选策略处理的冲突
+    ///
+    /// `remember` 为 true 且冲突属于某个批次时，记住该批次的选择，
+    /// 并一并取出同一批次中其他待处理的冲突。
+    pub fn take(&self, id: &str, strategy: &ConflictStrategy, remember: bool) -> Result<Vec<PendingConflict>> {
+        if *strategy == ConflictStrategy::Ask {
+            anyhow::bail!("请选择具体的处理方式");
+        }
+        let mut pending = self.pending.lock().map_err(|e| anyhow::anyhow!("无法获取冲突队列: {}", e))?;
+        let index = pending
+            .iter()
+            .position(|c| c.id == id)
+            .with_context(|| format!("待处理的冲突不存在: {}", id))?;
+        let conflict = pending.remove(index);
+
+        let batch = match (&conflict.batch, remember) {
+            (Some(batch), true) => batch.clone(),
+            _ => return Ok(vec![conflict]),
+        };
+        if let Ok(mut remembered) = self.remembered.lock() {
+            remembered.insert(batch.clone(), strategy.clone());
+        }
+        let (mut taken, rest): (Vec<_>, Vec<_>) = pending
+            .drain(..)
+            .partition(|c| c.batch.as_deref() == Some(batch.as_str()));
+        *pending = rest;
+        taken.insert(0, conflict);
+        Ok(taken)
+    }
+
+    /// 放弃处理（文件保留在原位置），返回冲突是否存在
+    pub fn dismiss(&self, id: &str) -> bool {
+        let mut pending = match self.pending.lock() {
+            Ok(p) => p,
+            Err(_) => return false,
+        };
+        let before = pending.len();
+        pending.retain(|c| c.id != id);
+        pending.len() < before
+    }
+}
+
+/// 源文件的修改时间是否晚于已有目标
+pub fn is_newer(source: &Path, existing: &Path) -> bool {
+    match (modified(source), modified(existing)) {
+        (Some(source), Some(existing)) => source > existing,
+        _ => false,
+    }
+}
+
+/// 源文件（或文件夹的总大小）是否大于已有目标
+pub fn is_larger(source: &Path, existing: &Path) -> bool {
+    transfer::total_size(source) > transfer::total_size(existing)
+}
+
+/// 内容是否相同（文件比较大小和 SHA-256，文件夹逐项比较）
+pub fn is_identical(source: &Path, existing: &Path) -> Result<bool> {
+    let (source_meta, existing_meta) = match (fs::metadata(source), fs::metadata(existing)) {
+        (Ok(s), Ok(e)) => (s, e),
+        _ => return Ok(false),
+    };
+
+    if source_meta.is_dir() && existing_meta.is_dir() {
+        let names = |dir: &Path| -> Result<BTreeSet<_>> {
+            Ok(fs::read_dir(dir)?.map(|e| e.map(|e| e.file_name())).collect::<Result<_, _>>()?)
+        };
+        let source_names = names(source)?;
+        if source_names != names(existing)? {
+            return Ok(false);
+        }
+        for name in source_names {
+            if !is_identical(&source.join(&name), &existing.join(&name))? {
+                return Ok(false);
+            }
+        }
+        return Ok(true);
+    }
+    if source_meta.is_dir() || existing_meta.is_dir() || source_meta.len() != existing_meta.len() {
+        return Ok(false);
+    }
+    Ok(transfer::hash_file(source)? == transfer::hash_file(existing)?)
+}
+
+/// 添加时间后缀的文件名（name_20261017-1530.ext）
+pub fn timestamped_name(stem: &str, extension: &str, time: DateTime<Local>) -> String {
+    let stem = format!("{}_{}", stem, time.format("%Y%m%d-%H%M"));
+    join_name(&stem, extension)
+}
+
+/// 目录中下一个可用的版本号文件名
+///
+/// 未带版本号的文件视为第 1 版；已有 name.v2.ext 和 name.v5.ext 时返回 name.v6.ext。
+/// 冲突的文件本身带有版本号时（name.v2.ext）按不带版本号的名称计算。
+pub fn versioned_name(dir: &Path, stem: &str, extension: &str) -> Result<String> {
+    let base = strip_version(stem);
+    let prefix = format!("{}.v", base);
+    let suffix = if extension.is_empty() { String::new() } else { format!(".{}", extension) };
+
+    let mut latest = 1;
+    for entry in fs::read_dir(dir).with_context(|| format!("无法读取目录: {:?}", dir))? {
+        let name = entry?.file_name();
+        let version = name
+            .to_str()
+            .and_then(|n| n.strip_prefix(&prefix))
+            .and_then(|n| n.strip_suffix(&suffix))
+            .and_then(|n| n.parse::<u32>().ok());
+        if let Some(version) = version {
+            latest = latest.max(version);
+        }
+    }
+
+    Ok(join_name(&format!("{}{}", prefix, latest + 1), extension))
+}
+
+/// 压缩包中已有同名条目时的处理方式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EntryDecision {
+    Skip,
+    /// 替换已有条目
+    Replace,
+    /// 以副本名称添加新条目
+    KeepBoth,
+}
+
+/// 压缩包中已有条目的信息
+pub struct ExistingEntry {
+    pub size: u64,
+    /// 修改时间（Unix 秒）
+    pub modified: Option<i64>,
+}
+
+/// 按冲突策略决定如何处理压缩包中的同名条目
+///
+/// 压缩包条目无法高效读取内容，内容是否相同按大小和修改时间判断；
+/// 时间和版本后缀的策略按副本名称添加。
+pub fn entry_decision(strategy: &ConflictStrategy, source: &Path, existing: &ExistingEntry, archive_path: &Path) -> Result<EntryDecision> {
+    let metadata = fs::metadata(source).with_context(|| format!("无法读取文件信息: {:?}", source))?;
+    let source_modified = metadata
+        .modified()
+        .ok()
+        .map(|t| DateTime::<Utc>::from(t).timestamp());
+
+    let decision = match strategy {
+        ConflictStrategy::Skip => EntryDecision::Skip,
+        ConflictStrategy::Overwrite => EntryDecision::Replace,
+        ConflictStrategy::Rename | ConflictStrategy::AppendTimestamp | ConflictStrategy::Versioned => EntryDecision::KeepBoth,
+        ConflictStrategy::KeepNewer => match (source_modified, existing.modified) {
+            (Some(source), Some(entry)) if source <= entry => EntryDecision::Skip,
+            _ => EntryDecision::Replace,
+        },
+        ConflictStrategy::KeepLarger if metadata.len() > existing.size => EntryDecision::Replace,
+        ConflictStrategy::KeepLarger => EntryDecision::Skip,
+        ConflictStrategy::SkipIfIdentical => {
+            let same_time = match (source_modified, existing.modified) {
+                (Some(source), Some(entry)) => (source - entry).abs() <= ENTRY_TIME_TOLERANCE_SECS,
+                _ => false,
+            };
+            if same_time && metadata.len() == existing.size {
+                EntryDecision::Skip
+            } else {
+                EntryDecision::KeepBoth
+            }
+        }
+        ConflictStrategy::Ask => {
+            return Err(ConflictPending { target: archive_path.to_path_buf() }.into());
+        }
+        // 压缩包条目是单个文件，调用方应传入合并时同名文件的策略
+        ConflictStrategy::Merge => EntryDecision::Skip,
+    };
+    Ok(decision)
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// 去掉结尾的版本号（report.v2 -> report）
+fn strip_version(stem: &str) -> &str {
+    match stem.rsplit_once(".v") {
+        Some((base, version)) if !base.is_empty() && !version.is_empty() && version.bytes().all(|b| b.is_ascii_digit()) => base,
+        _ => stem,
+    }
+}
+
+fn join_name(stem: &str, extension: &str) -> String {
+    if extension.is_empty() {
+        stem.to_string()
+    } else {
+        format!("{}.{}", stem, extension)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_names_and_comparisons() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        fs::create_dir_all(dir.join("a/sub")).unwrap();
+        fs::create_dir_all(dir.join("b/sub")).unwrap();
+        for name in ["report.pdf", "report.v2.pdf", "report.v5.pdf", "report.v2.txt", "a/sub/x.txt", "b/sub/x.txt"] {
+            fs::write(dir.join(name), "same").unwrap();
+        }
+
+        let time = Local.with_ymd_and_hms(2026, 10, 17, 15, 30, 0).unwrap();
+        assert_eq!(timestamped_name("name", "ext", time), "name_20261017-1530.ext");
+        assert_eq!(versioned_name(dir, "report", "pdf").unwrap(), "report.v6.pdf");
+        assert_eq!(versioned_name(dir, "report.v2", "pdf").unwrap(), "report.v6.pdf");
+        assert_eq!(versioned_name(dir, "notes", "").unwrap(), "notes.v2");
+
+        assert!(is_identical(&dir.join("report.pdf"), &dir.join("report.v2.pdf")).unwrap());
+        assert!(is_identical(&dir.join("a"), &dir.join("b")).unwrap());
+        fs::write(dir.join("b/sub/x.txt"), "diff").unwrap();
+        assert!(!is_identical(&dir.join("a"), &dir.join("b")).unwrap());
+        fs::write(dir.join("b/sub/x.txt"), "longer").unwrap();
+        assert!(is_larger(&dir.join("b"), &dir.join("a")));
+    }
+
+    #[test]
+    fn test_copy_name_formats() {
+        let zh = CopyNameFormat::localized("zh-CN");
+        assert_eq!(zh.name("a", "txt", 1), "a (副本).txt");
+        assert_eq!(zh.next_free("a", "txt", ["a.txt", "a (副本).txt"]), "a (副本 2).txt");
+        assert_eq!(zh.next_free("a", "txt", ["a.txt", "a (副本 2).txt"]), "a (副本).txt");
+        assert_eq!(CopyNameFormat::localized("en-US").name("a", "", 2), "a (2)");
+        assert!(CopyNameFormat::parse("{name}").is_err());
+        assert!(CopyNameFormat::parse("{name}/{n}").is_err());
+
+        let dashed = CopyNameFormat::parse("{name}-{n}").unwrap();
+        assert_eq!(dashed.name("report", "pdf", 2), "report-2.pdf");
+        let taken = ["report.pdf", "report-1.pdf", "report-2.pdf", "report-4.pdf", "report-x.pdf", "report-3.txt"];
+        assert_eq!(dashed.next_free("report", "pdf", taken), "report-3.pdf");
+
+        let many: Vec<String> = (1..=5000).map(|n| dashed.name("a", "", n)).collect();
+        assert_eq!(dashed.next_free("a", "", many.iter().map(String::as_str)), "a-5001");
+    }
+
+    #[test]
+    fn test_merge_policy() {
+        let policy = ConflictPolicy::new(ConflictStrategy::Merge, None).with_merge_strategy(ConflictStrategy::Ask);
+        assert_eq!(policy.merge_files, ConflictStrategy::Skip);
+
+        let policy = policy.with_merge_strategy(ConflictStrategy::KeepNewer);
+        assert_eq!(policy.file_strategy(), &ConflictStrategy::KeepNewer);
+        assert_eq!(policy.for_merged_files().strategy, ConflictStrategy::KeepNewer);
+        assert_eq!(ConflictPolicy::from(ConflictStrategy::Rename).file_strategy(), &ConflictStrategy::Rename);
+    }
+
+    #[test]
+    fn test_queue_remembers_batch_choice() {
+        let queue = ConflictQueue::default();
+        let action = RuleAction::Delete;
+        let park = |source: &str, batch: Option<&str>| {
+            let conflict = PendingConflict::new("r", &action, &[], source, Path::new("/t"), batch.map(String::from));
+            let id = conflict.id.clone();
+            queue.park(conflict);
+            id
+        };
+        let first = park("/a", Some("b1"));
+        park("/b", Some("b1"));
+        let other = park("/c", Some("b2"));
+
+        assert!(queue.take(&first, &ConflictStrategy::Ask, true).is_err());
+        let taken = queue.take(&first, &ConflictStrategy::Overwrite, true).unwrap();
+        assert_eq!(taken.iter().map(|c| c.source.as_str()).collect::<Vec<_>>(), ["/a", "/b"]);
+        assert_eq!(queue.remembered(Some("b1")), Some(ConflictStrategy::Overwrite));
+        assert_eq!(queue.remembered(Some("b2")), None);
+        assert!(queue.dismiss(&other));
+        assert!(queue.list().is_empty());
+    }
+}
@@ -0,0 +1,299 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// 序号占位符：{seq:名称} 或 {seq:名称:位数}
+static SEQ_PLACEHOLDER: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\{seq:([A-Za-z0-9_.\-]+)(?::(\d+))?\}").expect("序号占位符正则无效")
+});
+
+/// 计数器重置策略
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CounterReset {
+    /// 从不重置
+    #[default]
+    Never,
+    /// 每天重置
+    Daily,
+    /// 每月重置
+    Monthly,
+    /// 每个目标目录单独计数
+    PerDirectory,
+}
+
+/// 序号计数器定义（未定义的计数器使用默认设置）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SequenceCounter {
+    pub name: String,
+    /// 起始序号
+    #[serde(default = "default_start")]
+    pub start: u64,
+    /// 补零位数（0 表示不补零），可在占位符中单独指定
+    #[serde(default)]
+    pub padding: usize,
+    #[serde(default)]
+    pub reset: CounterReset,
+}
+
+fn default_start() -> u64 {
+    1
+}
+
+impl SequenceCounter {
+    /// 使用默认设置的计数器
+    fn with_name(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            start: default_start(),
+            padding: 0,
+            reset: CounterReset::default(),
+        }
+    }
+
+    /// 计算分组键：同一分组内序号连续递增
+    fn group_key(&self, now: DateTime<Local>, directory: &Path) -> String {
+        match self.reset {
+            CounterReset::Never => String::new(),
+            CounterReset::Daily => now.format("%Y-%m-%d").to_string(),
+            CounterReset::Monthly => now.format("%Y-%m").to_string(),
+            CounterReset::PerDirectory => directory.to_string_lossy().to_string(),
+        }
+    }
+}
+
+/// 模板中引用的计数器
+#[derive(Debug, Clone)]
+pub struct CounterRef {
+    pub name: String,
+    pub padding: Option<usize>,
+}
+
+/// 已分配的序号
+#[derive(Debug, Clone)]
+pub struct Allocation {
+    pub name: String,
+    pub key: String,
+    pub value: u64,
+}
+
+/// 查找模板中引用的所有计数器
+pub fn references(template: &str) -> Vec<CounterRef> {
+    SEQ_PLACEHOLDER
+        .captures_iter(template)
+        .map(|caps| CounterRef {
+            name: caps[1].to_string(),
+            padding: caps.get(2).and_then(|m| m.as_str().parse().ok()),
+        })
+        .collect()
+}
+
+/// 用已分配的序号替换模板中的序号占位符
+pub fn render(template: &str, definitions: &[SequenceCounter], allocations: &[Allocation]) -> String {
+    SEQ_PLACEHOLDER
+        .replace_all(template, |caps: &regex::Captures| {
+            let name = &caps[1];
+            let value = match allocations.iter().find(|a| a.name == name) {
+                Some(a) => a.value,
+                None => return caps[0].to_string(),
+            };
+            let padding = caps
+                .get(2)
+                .and_then(|m| m.as_str().parse().ok())
+                .or_else(|| definitions.iter().find(|c| c.name == name).map(|c| c.padding))
+                .unwrap_or(0);
+            format!("{:0width$}", value, width = padding)
+        })
+        .to_string()
+}
+
+/// 计数器状态（各计数器在各分组下已使用的最大序号）
+///
+/// 与配置文件分开保存，避免前端整体保存配置时覆盖已使用的序号。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CounterStore {
+    #[serde(skip)]
+    path: Option<PathBuf>,
+    /// 计数器名称 -> 分组键 -> 最后使用的序号
+    #[serde(default)]
+    values: BTreeMap<String, BTreeMap<String, u64>>,
+    /// 已预留但尚未确认的序号（计数器名称 -> 分组键 -> 序号），不保存
+    #[serde(skip)]
+    reserved: BTreeMap<String, BTreeMap<String, BTreeSet<u64>>>,
+}
+
+impl CounterStore {
+    /// 加载计数器状态，文件不存在时返回空状态
+    pub fn load_or_default<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let mut store = if path.exists() {
+            let content = fs::read_to_string(path)
+                .with_context(|| format!("无法读取计数器状态: {:?}", path))?;
+            serde_json::from_str(&content).with_context(|| "计数器状态文件格式错误")?
+        } else {
+            CounterStore::default()
+        };
+        store.path = Some(path.to_path_buf());
+        Ok(store)
+    }
+
+    /// 保存计数器状态
+    fn save(&self) -> Result<()> {
+        let path = match &self.path {
+            Some(p) => p,
+            None => return Ok(()),
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let content = serde_json::to_string_pretty(self)?;
+        fs::write(path, content).with_context(|| format!("无法写入计数器状态: {:?}", path))
+    }
+
+    /// 为模板中的计数器分配候选序号（`offset` 用于跳过已被占用的序号，不会修改状态）
+    ///
+    /// 已预留的序号视为已使用，从其后继续分配。
+    pub fn allocate(
+        &self,
+        refs: &[CounterRef],
+        definitions: &[SequenceCounter],
+        now: DateTime<Local>,
+        directory: &Path,
+        offset: u64,
+    ) -> Vec<Allocation> {
+        let mut allocations: Vec<Allocation> = Vec::new();
+        for r in refs {
+            if allocations.iter().any(|a| a.name == r.name) {
+                continue;
+            }
+            let definition = definitions
+                .iter()
+                .find(|c| c.name == r.name)
+                .cloned()
+                .unwrap_or_else(|| SequenceCounter::with_name(&r.name));
+            let key = definition.group_key(now, directory);
+            let last = self
+                .values
+                .get(&r.name)
+                .and_then(|groups| groups.get(&key))
+                .copied();
+            let reserved = self
+                .reserved
+                .get(&r.name)
+                .and_then(|groups| groups.get(&key))
+                .and_then(|values| values.last())
+                .copied();
+            let next = last.max(reserved).map_or(definition.start, |last| (last + 1).max(definition.start));
+
+            allocations.push(Allocation {
+                name: r.name.clone(),
+                key,
+                value: next + offset,
+            });
+        }
+        allocations
+    }
+
+    /// 预留序号，在确认或释放之前不会再分配给其他操作
+    pub fn reserve(&mut self, allocations: &[Allocation]) {
+        for a in allocations {
+            self.reserved
+                .entry(a.name.clone())
+                .or_default()
+                .entry(a.key.clone())
+                .or_default()
+                .insert(a.value);
+        }
+    }
+
+    /// 释放预留的序号（操作失败且没有产生目标文件时）
+    pub fn release(&mut self, allocations: &[Allocation]) {
+        for a in allocations {
+            let Some(groups) = self.reserved.get_mut(&a.name) else {
+                continue;
+            };
+            if let Some(values) = groups.get_mut(&a.key) {
+                values.remove(&a.value);
+                if values.is_empty() {
+                    groups.remove(&a.key);
+                }
+            }
+            if groups.is_empty() {
+                self.reserved.remove(&a.name);
+            }
+        }
+    }
+
+    /// 确认序号已被使用并保存（同时释放预留）
+    pub fn commit(&mut self, allocations: &[Allocation]) -> Result<()> {
+        self.release(allocations);
+        for a in allocations {
+            let last = self
+                .values
+                .entry(a.name.clone())
+                .or_default()
+                .entry(a.key.clone())
+                .or_insert(0);
+            *last = (*last).max(a.value);
+        }
+        self.save()
+    }
+
+    /// 获取所有计数器的当前状态
+    pub fn values(&self) -> &BTreeMap<String, BTreeMap<String, u64>> {
+        &self.values
+    }
+
+    /// 重置计数器（清除所有分组的已用序号）
+    pub fn reset(&mut self, name: &str) -> Result<()> {
+        self.values.remove(name);
+        info!("计数器已重置: {}", name);
+        self.save()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_allocate_and_render() {
+        let definitions = vec![SequenceCounter {
+            name: "scan".to_string(),
+            start: 1,
+            padding: 3,
+            reset: CounterReset::PerDirectory,
+        }];
+        let mut store = CounterStore::default();
+        let refs = references("Scan_{seq:scan}_{seq:page:2}");
+        let now = Local::now();
+
+        let first = store.allocate(&refs, &definitions, now, Path::new("/a"), 0);
+        assert_eq!(render("Scan_{seq:scan}_{seq:page:2}", &definitions, &first), "Scan_001_01");
+        store.commit(&first).unwrap();
+
+        // 跳过已占用的序号后确认，之后从更大的序号继续
+        let skipped = store.allocate(&refs, &definitions, now, Path::new("/a"), 1);
+        store.commit(&skipped).unwrap();
+        let next = store.allocate(&refs, &definitions, now, Path::new("/a"), 0);
+        assert_eq!(render("{seq:scan}", &definitions, &next), "004");
+
+        // 按目录计数时，其他目录从起始序号开始
+        let other = store.allocate(&refs, &definitions, now, Path::new("/b"), 0);
+        assert_eq!(render("{seq:scan}", &definitions, &other), "001");
+
+        // 预留的序号不会再分配，释放后可以重新使用
+        store.reserve(&other);
+        let concurrent = store.allocate(&refs, &definitions, now, Path::new("/b"), 0);
+        assert_eq!(render("{seq:scan}", &definitions, &concurrent), "002");
+        store.release(&other);
+        let again = store.allocate(&refs, &definitions, now, Path::new("/b"), 0);
+        assert_eq!(render("{seq:scan}", &definitions, &again), "001");
+    }
+}
@@ -0,0 +1,190 @@
+use crate::history::CommandOutput;
+use anyhow::{Context, Result};
+use std::env;
+use std::io::Read;
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// 每个输出流最多保留的字节数
+const MAX_OUTPUT_BYTES: usize = 64 * 1024;
+
+/// 超时结束进程后等待读取输出的最长时间
+const READER_JOIN_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// 始终传递给子进程的环境变量（查找程序和运行系统组件所必需）
+#[cfg(windows)]
+const BASE_ENV: &[&str] = &["PATH", "SYSTEMROOT", "TEMP", "TMP"];
+#[cfg(not(windows))]
+const BASE_ENV: &[&str] = &["PATH"];
+
+/// 外部命令的执行参数
+#[derive(Debug, Clone)]
+pub struct CommandSpec {
+    pub program: String,
+    pub args: Vec<String>,
+    pub working_dir: PathBuf,
+    pub timeout: Duration,
+    /// 允许传递给子进程的环境变量
+    pub env_allowlist: Vec<String>,
+}
+
+/// 运行外部命令并收集输出
+///
+/// 直接启动程序而不经过 shell，参数原样传递，因此文件名中的特殊字符不会被解释。
+/// 子进程只继承允许列表中的环境变量；超时后强制结束进程及其派生的进程。
+pub fn run(spec: &CommandSpec) -> Result<CommandOutput> {
+    let mut command = Command::new(&spec.program);
+    command
+        .args(&spec.args)
+        .current_dir(&spec.working_dir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .env_clear();
+
+    for name in BASE_ENV.iter().copied().chain(spec.env_allowlist.iter().map(String::as_str)) {
+        if let Some(value) = env::var_os(name) {
+            command.env(name, value);
+        }
+    }
+
+    // 子进程单独成组，超时时可以连同它派生的进程一起结束
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        command.process_group(0);
+    }
+
+    let started = Instant::now();
+    let mut child = command
+        .spawn()
+        .with_context(|| format!("无法启动程序: {}", spec.program))?;
+    info!("已启动外部命令: {} {:?}", spec.program, spec.args);
+
+    // 在独立线程中读取输出，避免管道写满导致子进程阻塞
+    let stdout = child.stdout.take().map(|s| thread::spawn(move || read_capped(s)));
+    let stderr = child.stderr.take().map(|s| thread::spawn(move || read_capped(s)));
+
+    let mut timed_out = false;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break Some(status);
+        }
+        if started.elapsed() >= spec.timeout {
+            warn!("外部命令超时（{} 秒），强制结束: {}", spec.timeout.as_secs(), spec.program);
+            kill_tree(&mut child);
+            timed_out = true;
+            break None;
+        }
+        thread::sleep(Duration::from_millis(50));
+    };
+
+    // 超时后输出管道可能仍被没能结束的进程占用，最多等待一段时间
+    let deadline = Instant::now() + READER_JOIN_TIMEOUT;
+    let collect = |handle: Option<thread::JoinHandle<String>>| {
+        let handle = handle?;
+        while timed_out && !handle.is_finished() && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(10));
+        }
+        if timed_out && !handle.is_finished() {
+            warn!("外部命令的输出管道仍被占用，放弃读取: {}", spec.program);
+            return None;
+        }
+        Some(handle.join().unwrap_or_default())
+    };
+
+    Ok(CommandOutput {
+        program: spec.program.clone(),
+        args: spec.args.clone(),
+        exit_code: status.and_then(|s| s.code()),
+        timed_out,
+        stdout: collect(stdout).unwrap_or_default(),
+        stderr: collect(stderr).unwrap_or_default(),
+        duration_ms: started.elapsed().as_millis() as u64,
+    })
+}
+
+/// 强制结束子进程及其派生的进程
+///
+/// Unix 上结束子进程所在的进程组，Windows 上结束整个进程树；失败时只结束子进程本身。
+fn kill_tree(child: &mut Child) {
+    let id = child.id().to_string();
+    #[cfg(unix)]
+    let mut killer = {
+        let mut command = Command::new("kill");
+        command.args(["-KILL", "--", &format!("-{}", id)]);
+        command
+    };
+    #[cfg(windows)]
+    let mut killer = {
+        let mut command = Command::new("taskkill");
+        command.args(["/T", "/F", "/PID", &id]);
+        command
+    };
+    #[cfg(any(unix, windows))]
+    let killed = killer
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success());
+    #[cfg(not(any(unix, windows)))]
+    let killed = false;
+
+    if !killed {
+        warn!("无法结束进程树，只结束子进程: {}", id);
+        let _ = child.kill();
+    }
+    let _ = child.wait();
+}
+
+/// 读取输出流，超出上限的部分丢弃（仍然读完，避免子进程阻塞）
+fn read_capped<R: Read>(mut reader: R) -> String {
+    let mut captured = Vec::new();
+    let mut buf = [0u8; 8192];
+    let mut truncated = false;
+
+    while let Ok(n) = reader.read(&mut buf) {
+        if n == 0 {
+            break;
+        }
+        let room = MAX_OUTPUT_BYTES.saturating_sub(captured.len());
+        if n > room {
+            truncated = true;
+        }
+        captured.extend_from_slice(&buf[..n.min(room)]);
+    }
+
+    let mut text = String::from_utf8_lossy(&captured).to_string();
+    if truncated {
+        text.push_str("\n...（输出过长，已截断）");
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_timeout_kills_spawned_processes() {
+        // 后台进程继承了输出管道，只结束 sh 时读取线程会一直等待
+        let spec = CommandSpec {
+            program: "sh".to_string(),
+            args: vec!["-c".to_string(), "echo started; sleep 30 & sleep 30".to_string()],
+            working_dir: env::temp_dir(),
+            timeout: Duration::from_millis(300),
+            env_allowlist: Vec::new(),
+        };
+
+        let started = Instant::now();
+        let output = run(&spec).unwrap();
+        assert!(output.timed_out);
+        assert_eq!(output.stdout.trim(), "started");
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
@@ -44,14 +44,14 @@ impl FileMonitor {
         }
         
         for folder in &enabled_folders {
-            let path_buf = PathBuf::from(&folder.path);
+            let path_buf = config.folder_path(folder);
             if path_buf.exists() {
                 watcher
                     .watch(&path_buf, RecursiveMode::NonRecursive) // 改为非递归
-                    .map_err(|e| format!("无法监控路径 {:?}: {}", folder.path, e))?;
-                info!("开始监控路径（仅根目录文件）: {} ({})", folder.name, folder.path);
+                    .map_err(|e| format!("无法监控路径 {:?}: {}", path_buf, e))?;
+                info!("开始监控路径（仅根目录文件）: {} ({})", folder.name, path_buf.display());
             } else {
-                warn!("监控路径不存在: {} ({})", folder.name, folder.path);
+                warn!("监控路径不存在: {} ({})", folder.name, path_buf.display());
             }
         }
 
@@ -92,16 +92,16 @@ impl FileMonitor {
         let enabled_folders: Vec<_> = config.folders.iter().filter(|f| f.enabled).collect();
         
         for folder in enabled_folders {
-            let path = Path::new(&folder.path);
+            let path = config.folder_path(folder);
             if !path.exists() || !path.is_dir() {
-                warn!("文件夹不存在或不是目录: {}", folder.path);
+                warn!("文件夹不存在或不是目录: {}", path.display());
                 continue;
             }
             
-            info!("扫描文件夹: {} ({})", folder.name, folder.path);
+            info!("扫描文件夹: {} ({})", folder.name, path.display());
             
             // 读取文件夹中的所有文件（非递归）
-            match fs::read_dir(path) {
+            match fs::read_dir(&path) {
                 Ok(entries) => {
                     let mut file_count = 0;
                     for entry in entries {
@@ -1,10 +1,124 @@
+use crate::archive::{self, ArchiveFormat, ExtractOptions};
+use crate::config::AppConfig;
+use crate::conflict::{self, ConflictPolicy, ConflictQueue, ConflictResolution, CopyNameFormat, PendingConflict};
+use crate::counters::{self, CounterStore, SequenceCounter};
+use crate::exec::{self, CommandSpec};
+use crate::history::{HistoryEntry, HistoryStatus, MergeEntry, MergeOutcome, RenameOutcome, RunHistory};
+use crate::image_convert::{self, ConvertOptions, OutputFormat};
+use crate::journal::TransferKind;
+use crate::path_vars;
+use crate::quarantine::Quarantine;
 use crate::models::{ConflictStrategy, FileInfo, Rule, RuleAction};
-use crate::rule_engine::RuleEngine;
+use crate::rule_engine::{RuleEngine, RuleMatch};
+use crate::transfer::{self, PreserveMetadata, Progress};
+use crate::trash_log::TrashLog;
 use anyhow::{Context, Result};
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Local, Utc};
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
 use std::fs;
 use std::path::{Path, PathBuf};
-use tracing::info;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+/// 为序号查找未被占用的目标时最多尝试的次数
+const MAX_SEQUENCE_PROBES: u64 = 10000;
+
+/// 文件因冲突放入待处理队列时的整理结果（文件仍在原位置，尚未整理）
+pub const CONFLICT_PENDING: &str = "文件等待处理冲突";
+
+/// 整理操作的上下文（规则以外的设置和共享状态）
+#[derive(Debug, Clone, Default)]
+pub struct OrganizeContext {
+    /// 序号计数器定义
+    pub counters: Vec<SequenceCounter>,
+    /// 序号计数器状态（多个整理任务共享）
+    pub counter_store: Arc<Mutex<CounterStore>>,
+    /// 用户定义的路径变量
+    pub path_variables: BTreeMap<String, String>,
+    /// 运行历史
+    pub history: Arc<RunHistory>,
+    /// 删除动作使用的隔离区（未启用时直接删除）
+    pub quarantine: Arc<Quarantine>,
+    /// 移到回收站的文件记录（用于撤销）
+    pub trash_log: Arc<TrashLog>,
+    /// 正在进行的复制操作（发送进度并支持取消）
+    pub operations: Arc<transfer::Operations>,
+    /// 复制时保留的元数据
+    pub preserve: PreserveMetadata,
+    /// 冲突策略为询问时的待处理队列
+    pub conflicts: Arc<ConflictQueue>,
+    /// 当前批次 ID（同一批次中可以记住冲突的处理方式）
+    pub batch: Option<String>,
+}
+
+impl OrganizeContext {
+    /// 根据配置创建整理上下文（不记录运行历史）
+    pub fn from_config(config: &AppConfig, counter_store: Arc<Mutex<CounterStore>>) -> Self {
+        Self {
+            counters: config.counters.clone(),
+            counter_store,
+            path_variables: config.path_variables.clone(),
+            history: Arc::default(),
+            quarantine: Arc::default(),
+            trash_log: Arc::default(),
+            operations: Arc::default(),
+            preserve: config.preserve_metadata,
+            conflicts: Arc::default(),
+            batch: None,
+        }
+    }
+
+    /// 记录运行历史
+    pub fn with_history(mut self, history: Arc<RunHistory>) -> Self {
+        self.history = history;
+        self
+    }
+
+    /// 删除时移入隔离区
+    pub fn with_quarantine(mut self, quarantine: Arc<Quarantine>) -> Self {
+        self.quarantine = quarantine;
+        self
+    }
+
+    /// 记录移到回收站的文件
+    pub fn with_trash_log(mut self, trash_log: Arc<TrashLog>) -> Self {
+        self.trash_log = trash_log;
+        self
+    }
+
+    /// 复制时发送进度并支持取消
+    pub fn with_operations(mut self, operations: Arc<transfer::Operations>) -> Self {
+        self.operations = operations;
+        self
+    }
+
+    /// 冲突策略为询问时放入该队列
+    pub fn with_conflicts(mut self, conflicts: Arc<ConflictQueue>) -> Self {
+        self.conflicts = conflicts;
+        self
+    }
+
+    /// 设置批次 ID
+    pub fn with_batch(mut self, batch: Option<String>) -> Self {
+        self.batch = batch;
+        self
+    }
+
+    /// 规则实际使用的冲突处理方式（询问时优先使用本批次记住的选择）
+    fn conflict_policy(&self, rule: &Rule) -> ConflictPolicy {
+        let strategy = match &rule.conflict_strategy {
+            ConflictStrategy::Ask => self
+                .conflicts
+                .remembered(self.batch.as_deref())
+                .unwrap_or(ConflictStrategy::Ask),
+            other => other.clone(),
+        };
+        ConflictPolicy::new(strategy, rule.copy_name_format.as_deref())
+            .with_merge_strategy(rule.merge_file_strategy.clone())
+    }
+}
 
 /// 获取文件信息
 pub fn get_file_info(path: &Path) -> Result<FileInfo> {
@@ -45,24 +159,384 @@ pub fn get_file_info(path: &Path) -> Result<FileInfo> {
 }
 
 /// 整理文件（根据规则）
-pub fn organize_file(file_info: &FileInfo, rules: &[Rule]) -> Result<Option<String>> {
+pub fn organize_file(file_info: &FileInfo, rules: &[Rule], ctx: &OrganizeContext) -> Result<Option<String>> {
     let engine = RuleEngine::new(rules.to_vec());
 
-    // 查找匹配的规则（获取匹配结果，包含正则捕获组）
-    let rule_match = match engine.find_matching_rule(file_info) {
-        Some(m) => m,
-        None => return Ok(None),
+    // 查找所有应执行的规则（获取匹配结果，包含正则捕获组）
+    let matches = engine.find_matching_rules(file_info);
+    if matches.is_empty() {
+        return Ok(None);
+    }
+
+    // 依次执行各规则的动作流水线，后一条规则作用于前一条规则处理后的文件
+    let mut current = file_info.clone();
+    let mut last_output = None;
+
+    for rule_match in &matches {
+        info!("应用规则 '{}' 到文件 {}", rule_match.rule.name, current.name);
+
+        let (output, subject) = execute_pipeline(rule_match.rule, &current, &engine, &rule_match.regex_captures, ctx)?;
+        if output.is_some() {
+            last_output = output;
+        }
+
+        match subject {
+            Some(next) => current = next,
+            None => break,
+        }
+    }
+
+    Ok(last_output)
+}
+
+/// 按顺序执行规则的动作流水线
+///
+/// 每一步都以上一步处理后的文件作为输入：移动和重命名会改变文件位置，
+/// 复制只产生副本，后续步骤仍作用于原文件。任一步失败即停止，
+/// 文件被删除、移动或重命名被跳过或外部命令要求跳过时，剩余步骤不再执行。
+/// 冲突策略为询问且目标已存在时，文件放入待处理队列，剩余步骤同样不再执行。
+/// 每一步的结果都会写入运行历史。
+///
+/// 返回最后一步的输出（放入待处理队列时为 [`CONFLICT_PENDING`]），
+/// 以及流水线结束后文件的当前信息（已删除或被跳过时为 None）。
+fn execute_pipeline(rule: &Rule, file_info: &FileInfo, engine: &RuleEngine, regex_captures: &[String], ctx: &OrganizeContext) -> Result<(Option<String>, Option<FileInfo>)> {
+    if rule.actions.is_empty() {
+        warn!("规则 '{}' 没有配置任何动作", rule.name);
+        return Ok((None, Some(file_info.clone())));
+    }
+
+    let total = rule.actions.len();
+    let policy = ctx.conflict_policy(rule);
+    let mut current = file_info.clone();
+    let mut last_output = None;
+
+    for (index, action) in rule.actions.iter().enumerate() {
+        let step = index + 1;
+
+        if let RuleAction::Exec { .. } = action {
+            let proceed = exec_step(rule, action, &current, engine, regex_captures, ctx)
+                .with_context(|| format!("规则 '{}' 第 {}/{} 步执行失败", rule.name, step, total))?;
+            if !proceed {
+                if step < total {
+                    info!("外部命令要求跳过，停止执行剩余 {} 步: {}", total - step, current.path);
+                }
+                return Ok((last_output, Some(current)));
+            }
+            continue;
+        }
+
+        let mut entry = HistoryEntry::new(&rule.name, action.kind(), &current.path, HistoryStatus::Success);
+        let result = execute_step(action, &current, engine, &policy, regex_captures, ctx, &mut entry);
+        let output = match result {
+            Ok(output) => output,
+            Err(e) => {
+                if let Some(target) = conflict::pending_target(&e) {
+                    let conflict = PendingConflict::new(&rule.name, action, regex_captures, &current.path, target, ctx.batch.clone())
+                        .with_rule_settings(rule);
+                    ctx.conflicts.park(conflict);
+                    entry.status = HistoryStatus::Skipped;
+                    entry.message = Some(format!("{:#}", e));
+                    ctx.history.record(&entry);
+                    if step < total {
+                        info!("等待处理冲突，停止执行剩余 {} 步: {}", total - step, current.path);
+                    }
+                    // 文件仍在当前位置，不算已整理
+                    return Ok((Some(CONFLICT_PENDING.to_string()), None));
+                }
+
+                entry.status = HistoryStatus::Failed;
+                entry.message = Some(format!("{:#}", e));
+                ctx.history.record(&entry);
+                return Err(e.context(format!("规则 '{}' 第 {}/{} 步执行失败", rule.name, step, total)));
+            }
+        };
+
+        // 目标已存在且策略为跳过时，文件仍在原位置；
+        // 合并文件夹时源文件夹有剩余项也仍在，按各项结果判断
+        let merged = (!entry.merge.is_empty()).then(|| entry.apply_merge_status());
+        let skipped = match action {
+            RuleAction::MoveTo { destination } => destination != "{recycle}" && merged.map_or_else(|| Path::new(&current.path).exists(), |m| !m),
+            RuleAction::MoveAndLink { .. } => merged.map_or_else(|| !is_symlink(Path::new(&current.path)), |m| !m),
+            // 合并后源文件夹仍有剩余项时不能继续，否则后续步骤会作用于目标中已有的文件夹
+            RuleAction::Rename { .. } | RuleAction::Sanitize { .. } => {
+                entry.rename == Some(RenameOutcome::Skipped)
+                    || (merged.is_some() && Path::new(&current.path).symlink_metadata().is_ok())
+            }
+            RuleAction::ConvertImage { trash_original: true, .. } | RuleAction::Archive { .. } => output.is_none(),
+            _ => false,
+        };
+        entry.output = output.clone();
+        if skipped && entry.status == HistoryStatus::Success {
+            entry.status = HistoryStatus::Skipped;
+        }
+        ctx.history.record(&entry);
+
+        if skipped {
+            if step < total {
+                info!("移动或重命名被跳过，停止执行剩余 {} 步: {}", total - step, current.path);
+            }
+            return Ok((output, None));
+        }
+
+        match action {
+            RuleAction::MoveTo { destination } if destination != "{recycle}" => {
+                if let Some(new_path) = &output {
+                    current = get_file_info(Path::new(new_path))?;
+                }
+            }
+            RuleAction::MoveAndLink { .. } | RuleAction::Rename { .. } | RuleAction::Sanitize { .. } => {
+                if let Some(new_path) = &output {
+                    current = get_file_info(Path::new(new_path))?;
+                }
+            }
+            RuleAction::CopyTo { .. }
+            | RuleAction::Symlink { .. }
+            | RuleAction::Hardlink { .. }
+            | RuleAction::Exec { .. }
+            | RuleAction::Extract { trash_after: false, .. }
+            | RuleAction::Archive { keep_original: true, .. }
+            | RuleAction::ConvertImage { trash_original: false, .. } => {}
+            RuleAction::MoveTo { .. }
+            | RuleAction::Delete
+            | RuleAction::Extract { .. }
+            | RuleAction::Archive { .. }
+            | RuleAction::ConvertImage { .. } => {
+                // 文件已删除或移动到回收站，流水线到此结束
+                if step < total {
+                    info!("文件已被删除，跳过剩余 {} 步: {}", total - step, current.path);
+                }
+                return Ok((output, None));
+            }
+        }
+
+        last_output = output;
+    }
+
+    Ok((last_output, Some(current)))
+}
+
+/// 执行外部命令步骤并记录运行历史，返回是否继续执行剩余步骤
+///
+/// 退出码在成功列表中时继续，在跳过列表中时停止该规则的剩余步骤，
+/// 其他退出码、超时或无法启动程序都视为失败。
+fn exec_step(rule: &Rule, action: &RuleAction, file_info: &FileInfo, engine: &RuleEngine, regex_captures: &[String], ctx: &OrganizeContext) -> Result<bool> {
+    let RuleAction::Exec { program, args, working_dir, timeout_secs, env, success_codes, skip_codes } = action else {
+        anyhow::bail!("不是外部命令动作");
+    };
+
+    let mut entry = HistoryEntry::new(&rule.name, action.kind(), &file_info.path, HistoryStatus::Success);
+    let source_path = Path::new(&file_info.path);
+    let source_dir = source_path.parent().unwrap_or(Path::new("."));
+
+    let resolve = |template: &str| engine.resolve_command_arg(template, file_info, regex_captures);
+    let args = if args.is_empty() {
+        vec![file_info.path.clone()]
+    } else {
+        args.iter().map(|a| resolve(a)).collect()
+    };
+    let working_dir = match working_dir {
+        Some(dir) => {
+            let dir = path_vars::expand(&resolve(dir), &ctx.path_variables)?;
+            source_dir.join(dir)
+        }
+        None => source_dir.to_path_buf(),
+    };
+
+    let spec = CommandSpec {
+        program: path_vars::expand(program, &ctx.path_variables)?,
+        args,
+        working_dir,
+        timeout: Duration::from_secs(*timeout_secs),
+        env_allowlist: env.clone(),
+    };
+
+    let command = match exec::run(&spec) {
+        Ok(command) => command,
+        Err(e) => {
+            entry.status = HistoryStatus::Failed;
+            entry.message = Some(format!("{:#}", e));
+            ctx.history.record(&entry);
+            return Err(e);
+        }
+    };
+
+    let exit_code = command.exit_code;
+    let timed_out = command.timed_out;
+    entry.command = Some(command);
+
+    let result = match exit_code {
+        Some(code) if success_codes.contains(&code) => Ok(true),
+        Some(code) if skip_codes.contains(&code) => {
+            entry.status = HistoryStatus::Skipped;
+            entry.message = Some(format!("退出码 {} 表示跳过", code));
+            Ok(false)
+        }
+        _ => {
+            let reason = match exit_code {
+                _ if timed_out => format!("执行超时（{} 秒）", timeout_secs),
+                Some(code) => format!("退出码 {}", code),
+                None => "进程被终止".to_string(),
+            };
+            entry.status = HistoryStatus::Failed;
+            entry.message = Some(reason.clone());
+            Err(anyhow::anyhow!("外部命令执行失败: {} ({})", spec.program, reason))
+        }
+    };
+
+    ctx.history.record(&entry);
+    result
+}
+
+/// 预览匹配规则的最终结果（不实际操作文件）
+///
+/// 依次推演每条规则的动作流水线，返回最后一步输出的完整路径；
+/// 移动到回收站时返回 `{recycle}`，删除文件时返回 None。
+/// 序号占位符显示为下一个可用序号，但不会占用该序号。
+pub fn preview_matches(matches: &[RuleMatch], file_info: &FileInfo, engine: &RuleEngine, ctx: &OrganizeContext) -> Option<String> {
+    let mut current = file_info.clone();
+    let mut last_output = None;
+
+    for rule_match in matches {
+        for action in &rule_match.rule.actions {
+            if let RuleAction::MoveTo { destination } = action {
+                if destination == "{recycle}" {
+                    return Some(destination.clone());
+                }
+            }
+
+            let action = match expand_destination(action, ctx) {
+                Ok(action) => preview_sequences(&action, &current, engine, &rule_match.regex_captures, ctx),
+                Err(e) => {
+                    warn!("规则 '{}' 的目标路径无法展开: {}", rule_match.rule.name, e);
+                    return None;
+                }
+            };
+            let target = planned_target(&action, &current, engine, &rule_match.regex_captures);
+
+            match action {
+                RuleAction::MoveTo { .. }
+                | RuleAction::MoveAndLink { .. }
+                | RuleAction::Rename { .. }
+                | RuleAction::Sanitize { .. } => {
+                    let target = target?;
+                    current = predicted_file_info(&current, &target);
+                    last_output = Some(target.to_string_lossy().to_string());
+                }
+                RuleAction::Exec { .. } => {}
+                RuleAction::CopyTo { .. }
+                | RuleAction::Symlink { .. }
+                | RuleAction::Hardlink { .. }
+                | RuleAction::Extract { trash_after: false, .. }
+                | RuleAction::Archive { keep_original: true, .. }
+                | RuleAction::ConvertImage { trash_original: false, .. } => {
+                    last_output = Some(target?.to_string_lossy().to_string());
+                }
+                RuleAction::Extract { .. } | RuleAction::Archive { .. } | RuleAction::ConvertImage { .. } => {
+                    return Some(target?.to_string_lossy().to_string());
+                }
+                RuleAction::Delete => return None,
+            }
+        }
+    }
+
+    last_output
+}
+
+/// 计算动作的目标文件路径（不实际操作文件）
+fn planned_target(action: &RuleAction, file_info: &FileInfo, engine: &RuleEngine, regex_captures: &[String]) -> Option<PathBuf> {
+    let base_path = Path::new(&file_info.path).parent().unwrap_or(Path::new("."));
+    let dest = engine.get_destination_path(action, file_info, base_path, regex_captures)?;
+
+    match action {
+        RuleAction::MoveTo { .. }
+        | RuleAction::CopyTo { .. }
+        | RuleAction::MoveAndLink { .. }
+        | RuleAction::Symlink { .. }
+        | RuleAction::Hardlink { .. } => {
+            if dest == "{recycle}" {
+                None
+            } else {
+                Some(Path::new(&dest).join(&file_info.name))
+            }
+        }
+        RuleAction::Rename { .. } | RuleAction::Sanitize { .. } | RuleAction::Archive { .. } => Some(PathBuf::from(dest)),
+        RuleAction::ConvertImage { format, .. } => {
+            let format = OutputFormat::parse(format).ok()?;
+            Some(Path::new(&dest).join(image_convert::output_name(&file_info.name, format)))
+        }
+        RuleAction::Delete | RuleAction::Exec { .. } => None,
+        RuleAction::Extract { create_subfolder, .. } => {
+            let dest = PathBuf::from(dest);
+            if !*create_subfolder {
+                return Some(dest);
+            }
+            let (_, stem) = ArchiveFormat::detect(&file_info.name)?;
+            Some(dest.join(stem))
+        }
+    }
+}
+
+/// 展开目标路径中的路径变量
+fn expand_destination(action: &RuleAction, ctx: &OrganizeContext) -> Result<RuleAction> {
+    match action.destination() {
+        Some(destination) => {
+            let expanded = path_vars::expand(destination, &ctx.path_variables)?;
+            Ok(action.with_template(expanded))
+        }
+        None => Ok(action.clone()),
+    }
+}
+
+/// 预览时用下一个可用序号替换序号占位符
+fn preview_sequences(action: &RuleAction, file_info: &FileInfo, engine: &RuleEngine, regex_captures: &[String], ctx: &OrganizeContext) -> RuleAction {
+    let template = match action.template() {
+        Some(t) => t,
+        None => return action.clone(),
     };
+    let refs = counters::references(template);
+    if refs.is_empty() {
+        return action.clone();
+    }
 
-    info!("应用规则 '{}' 到文件 {}", rule_match.rule.name, file_info.name);
+    let store = match ctx.counter_store.lock() {
+        Ok(store) => store,
+        Err(_) => return action.clone(),
+    };
+    let directory = planned_target(action, file_info, engine, regex_captures)
+        .and_then(|p| p.parent().map(Path::to_path_buf))
+        .unwrap_or_default();
+    let allocations = store.allocate(&refs, &ctx.counters, Local::now(), &directory, 0);
+    action.with_template(counters::render(template, &ctx.counters, &allocations))
+}
 
-    // 执行规则动作，传递冲突处理策略和正则捕获组
-    execute_action(&rule_match.rule.action, file_info, &engine, &rule_match.rule.conflict_strategy, &rule_match.regex_captures)
+/// 根据预测的新路径构造文件信息（保留大小和时间）
+fn predicted_file_info(file_info: &FileInfo, path: &Path) -> FileInfo {
+    FileInfo {
+        path: path.to_string_lossy().to_string(),
+        name: path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("")
+            .to_string(),
+        extension: path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("")
+            .to_string(),
+        ..file_info.clone()
+    }
 }
 
 /// 手动整理单个文件
-pub fn organize_single_file(file_path: &str, rules: &[Rule]) -> Result<String> {
+pub fn organize_single_file(file_path: &str, rules: &[Rule], ctx: &OrganizeContext) -> Result<String> {
     let path = Path::new(file_path);
+    
+    // 符号链接（例如移动后留在原位置的链接）不参与整理，避免重复处理
+    if is_symlink(path) {
+        info!("跳过符号链接: {}", file_path);
+        return Ok("文件未匹配任何规则".to_string());
+    }
+    
     let file_info = get_file_info(path)?;
 
     // 记录日志时区分文件和文件夹
@@ -72,7 +546,11 @@ pub fn organize_single_file(file_path: &str, rules: &[Rule]) -> Result<String> {
         info!("正在检查文件是否匹配规则: {}", file_path);
     }
     
-    match organize_file(&file_info, rules)? {
+    match organize_file(&file_info, rules, ctx)? {
+        Some(pending) if pending == CONFLICT_PENDING => {
+            info!("目标已存在，等待处理冲突: {}", file_path);
+            Ok(pending)
+        }
         Some(new_path) => {
             if file_info.is_directory {
                 info!("✓ 文件夹已整理: {} -> {}", file_path, new_path);
@@ -92,8 +570,113 @@ pub fn organize_single_file(file_path: &str, rules: &[Rule]) -> Result<String> {
     }
 }
 
+/// 按所选策略处理等待中的冲突
+///
+/// 重新执行遇到冲突的动作，结果写入运行历史；原规则中剩余的步骤不再执行。
+pub fn resolve_conflict(conflict: &PendingConflict, strategy: &ConflictStrategy, ctx: &OrganizeContext) -> ConflictResolution {
+    let engine = RuleEngine::new(Vec::new());
+    let policy = conflict.policy(strategy);
+    let result = get_file_info(Path::new(&conflict.source)).and_then(|file_info| {
+        let mut entry = HistoryEntry::new(&conflict.rule_name, conflict.action.kind(), &conflict.source, HistoryStatus::Success);
+        let result = execute_step(&conflict.action, &file_info, &engine, &policy, &conflict.regex_captures, ctx, &mut entry);
+        match &result {
+            Ok(output) => {
+                entry.output = output.clone();
+                if !entry.merge.is_empty() {
+                    entry.apply_merge_status();
+                }
+            }
+            Err(e) => {
+                entry.status = HistoryStatus::Failed;
+                entry.message = Some(format!("{:#}", e));
+            }
+        }
+        ctx.history.record(&entry);
+        result
+    });
+
+    match result {
+        Ok(output) => {
+            info!("冲突已处理 ({:?}): {}", strategy, conflict.source);
+            ConflictResolution { id: conflict.id.clone(), source: conflict.source.clone(), output, error: None }
+        }
+        Err(e) => {
+            warn!("处理冲突失败: {}: {:#}", conflict.source, e);
+            ConflictResolution { id: conflict.id.clone(), source: conflict.source.clone(), output: None, error: Some(format!("{:#}", e)) }
+        }
+    }
+}
+
+/// 执行单个步骤：模板中包含序号占位符时，先分配序号再执行动作
+///
+/// 序号在计数器锁内分配并预留，执行动作时不持有锁，因此其他文件的整理
+/// 不必等待这次移动完成，也不会分到同一个序号。
+/// 只有动作成功或目标文件已经产生时序号才会被确认，否则释放预留；
+/// 已存在同名目标的序号会被跳过，因此失败的移动不会导致序号重复。
+fn execute_step(action: &RuleAction, file_info: &FileInfo, engine: &RuleEngine, policy: &ConflictPolicy, regex_captures: &[String], ctx: &OrganizeContext, entry: &mut HistoryEntry) -> Result<Option<String>> {
+    let action = &expand_destination(action, ctx)?;
+    let template = match action.template() {
+        Some(t) => t,
+        None => return execute_action(action, file_info, engine, policy, regex_captures, ctx, entry),
+    };
+    let refs = counters::references(template);
+    if refs.is_empty() {
+        return execute_action(action, file_info, engine, policy, regex_captures, ctx, entry);
+    }
+
+    let lock_store = || ctx.counter_store
+        .lock()
+        .map_err(|e| anyhow::anyhow!("无法获取计数器状态: {}", e));
+    let now = Local::now();
+
+    // 按重置策略分组时使用的目标目录（序号占位符保持原样）
+    let directory = planned_target(action, file_info, engine, regex_captures)
+        .and_then(|p| p.parent().map(Path::to_path_buf))
+        .unwrap_or_default();
+
+    let (concrete, allocations, target) = {
+        let mut store = lock_store()?;
+
+        // 跳过已被占用的序号（例如之前失败的操作遗留的文件）
+        let mut offset = 0;
+        let (concrete, allocations, target) = loop {
+            let allocations = store.allocate(&refs, &ctx.counters, now, &directory, offset);
+            let concrete = action.with_template(counters::render(template, &ctx.counters, &allocations));
+            let target = planned_target(&concrete, file_info, engine, regex_captures);
+
+            let occupied = target.as_ref().is_some_and(|t| t.exists());
+            if !occupied || offset >= MAX_SEQUENCE_PROBES {
+                break (concrete, allocations, target);
+            }
+            offset += 1;
+        };
+        store.reserve(&allocations);
+        (concrete, allocations, target)
+    };
+
+    let result = execute_action(&concrete, file_info, engine, policy, regex_captures, ctx, entry);
+    let mut store = lock_store()?;
+    match result {
+        Ok(output) => {
+            store.commit(&allocations)?;
+            Ok(output)
+        }
+        Err(e) => {
+            // 目标文件已经产生（例如复制成功但删除源文件失败）时，序号视为已使用
+            if target.is_some_and(|t| t.exists()) {
+                store.commit(&allocations)?;
+            } else {
+                store.release(&allocations);
+            }
+            Err(e)
+        }
+    }
+}
+
 /// 执行规则动作
-fn execute_action(action: &RuleAction, file_info: &FileInfo, engine: &RuleEngine, conflict_strategy: &ConflictStrategy, regex_captures: &[String]) -> Result<Option<String>> {
+///
+/// 需要记录到运行历史的额外信息（例如校验值）写入 `entry`。
+fn execute_action(action: &RuleAction, file_info: &FileInfo, engine: &RuleEngine, policy: &ConflictPolicy, regex_captures: &[String], ctx: &OrganizeContext, entry: &mut HistoryEntry) -> Result<Option<String>> {
     let source_path = Path::new(&file_info.path);
     let base_path = source_path.parent().unwrap_or(Path::new("."));
 
@@ -101,16 +684,18 @@ fn execute_action(action: &RuleAction, file_info: &FileInfo, engine: &RuleEngine
         RuleAction::MoveTo { destination } => {
             // 检查是否为回收站特殊路径
             if destination == "{recycle}" {
-                move_to_recycle_bin(source_path)?;
+                ctx.trash_log.trash(source_path)?;
                 Ok(Some("已移动到回收站".to_string()))
             } else {
                 let dest_dir = engine
                     .get_destination_path(action, file_info, base_path, regex_captures)
                     .context("无法获取目标路径")?;
                 
-                // move_file_with_strategy 返回实际的目标文件完整路径
-                let final_path = move_file_with_strategy(source_path, &dest_dir, conflict_strategy)?;
-                Ok(Some(final_path))
+                // 返回实际的目标文件完整路径
+                let outcome = move_file_checked(source_path, &dest_dir, policy, Some(ctx))?;
+                entry.checksum = outcome.checksum;
+                entry.merge = outcome.merged;
+                Ok(Some(outcome.path))
             }
         }
 
@@ -120,84 +705,172 @@ fn execute_action(action: &RuleAction, file_info: &FileInfo, engine: &RuleEngine
                 .context("无法获取目标路径")?;
             
             // copy_file_with_strategy 返回实际的目标文件完整路径
-            let final_path = copy_file_with_strategy(source_path, &dest_dir, conflict_strategy)?;
+            let outcome = copy_file_with_strategy(source_path, &dest_dir, policy, ctx)?;
+            entry.checksum = outcome.checksum;
+            entry.merge = outcome.merged;
+            Ok(Some(outcome.path))
+        }
+
+        RuleAction::MoveAndLink { .. } => {
+            let dest_dir = engine
+                .get_destination_path(action, file_info, base_path, regex_captures)
+                .context("无法获取目标路径")?;
+            
+            let outcome = move_file_checked(source_path, &dest_dir, policy, Some(ctx))?;
+            entry.checksum = outcome.checksum;
+            entry.merge = outcome.merged;
+            let final_path = outcome.path;
+            
+            // 移动被跳过时原文件仍在，不创建链接
+            if !source_path.exists() {
+                create_symlink(Path::new(&final_path), source_path)?;
+                info!("已在原位置创建链接: {:?} -> {}", source_path, final_path);
+            }
+            Ok(Some(final_path))
+        }
+
+        RuleAction::Symlink { .. } | RuleAction::Hardlink { .. } => {
+            let dest_dir = engine
+                .get_destination_path(action, file_info, base_path, regex_captures)
+                .context("无法获取目标路径")?;
+            
+            let hard = matches!(action, RuleAction::Hardlink { .. });
+            let final_path = link_file_with_strategy(source_path, &dest_dir, policy, hard)?;
             Ok(Some(final_path))
         }
 
-        RuleAction::Rename { pattern: _ } => {
+        // 外部命令需要记录输出并决定是否继续，由流水线单独执行
+        RuleAction::Exec { .. } => anyhow::bail!("外部命令只能在规则流水线中执行"),
+
+        RuleAction::Rename { .. } | RuleAction::Sanitize { .. } => {
             let new_path = engine
                 .get_destination_path(action, file_info, base_path, regex_captures)
                 .context("无法获取新文件名")?;
             
-            fs::rename(source_path, &new_path)
-                .with_context(|| format!("重命名文件失败: {:?} -> {}", source_path, new_path))?;
-            
-            info!("文件已重命名: {:?} -> {}", source_path, new_path);
-            Ok(Some(new_path))
+            let final_dest = rename_with_policy(source_path, Path::new(&new_path), policy, entry)?;
+            Ok(Some(final_dest.to_string_lossy().to_string()))
         }
 
         RuleAction::Delete => {
-            fs::remove_file(source_path)
-                .with_context(|| format!("删除文件失败: {:?}", source_path))?;
+            // 启用隔离区时先移入隔离区，保留期满后再彻底删除
+            if ctx.quarantine.is_enabled() {
+                ctx.quarantine.put(source_path)?;
+                return Ok(Some("已删除".to_string()));
+            }
+            
+            // 不跟随符号链接，避免删除链接指向的目录
+            if fs::symlink_metadata(source_path).is_ok_and(|m| m.is_dir()) {
+                fs::remove_dir_all(source_path)
+                    .with_context(|| format!("删除文件夹失败: {:?}", source_path))?;
+            } else {
+                fs::remove_file(source_path)
+                    .with_context(|| format!("删除文件失败: {:?}", source_path))?;
+            }
             
             info!("文件已删除: {:?}", source_path);
             Ok(Some("已删除".to_string()))
         }
+
+        RuleAction::Extract { create_subfolder, flatten_single_root, trash_after, .. } => {
+            let dest_dir = engine
+                .get_destination_path(action, file_info, base_path, regex_captures)
+                .context("无法获取目标路径")?;
+            
+            let options = ExtractOptions {
+                create_subfolder: *create_subfolder,
+                flatten_single_root: *flatten_single_root,
+            };
+            let extracted = archive::extract(source_path, Path::new(&dest_dir), &options, policy)?;
+            
+            // 所有条目都放到最终位置后才处理原压缩包，有条目被跳过时保留
+            if extracted.total > 0 && extracted.placed == 0 {
+                entry.status = HistoryStatus::Skipped;
+            } else if !extracted.is_complete() {
+                entry.message = Some(format!("已解压 {}/{} 项，其余已跳过", extracted.placed, extracted.total));
+            }
+            if *trash_after && extracted.is_complete() {
+                ctx.trash_log.trash(source_path)?;
+            }
+            Ok(Some(extracted.path))
+        }
+
+        RuleAction::Archive { keep_original, .. } => {
+            let archive_path = engine
+                .get_destination_path(action, file_info, base_path, regex_captures)
+                .context("无法获取压缩包路径")?;
+            
+            // 条目写入并校验通过后才删除原文件
+            // 已存在同名条目且策略为跳过时没有写入，原文件保留
+            if archive::append_to_archive(source_path, Path::new(&archive_path), policy)?.is_none() {
+                return Ok(None);
+            }
+            if !*keep_original {
+                fs::remove_file(source_path)
+                    .with_context(|| format!("删除已归档的原文件失败: {:?}", source_path))?;
+                info!("原文件已归档并删除: {:?}", source_path);
+            }
+            Ok(Some(archive_path))
+        }
+
+        RuleAction::ConvertImage { format, max_dimension, quality, strip_metadata, trash_original, .. } => {
+            let dest_dir = engine
+                .get_destination_path(action, file_info, base_path, regex_captures)
+                .context("无法获取目标路径")?;
+            
+            let options = ConvertOptions::new(format, *max_dimension, *quality, *strip_metadata)?;
+            let output = match image_convert::convert(source_path, &dest_dir, &options, policy)? {
+                Some(output) => output,
+                None => return Ok(None),
+            };
+            
+            // 转换成功后才处理原图；输出覆盖了原图时不能再删除
+            if *trash_original && Path::new(&output) != source_path {
+                ctx.trash_log.trash(source_path)?;
+            }
+            Ok(Some(output))
+        }
     }
 }
 
-/// 移动文件到回收站
-fn move_to_recycle_bin(source: &Path) -> Result<()> {
-    trash::delete(source)
-        .with_context(|| format!("移动文件到回收站失败: {:?}", source))?;
-    
-    info!("文件已移动到回收站: {:?}", source);
-    Ok(())
+/// 冲突处理结果
+pub(crate) enum Resolution {
+    /// 写入到该路径
+    Proceed(PathBuf),
+    /// 目标已存在且策略为跳过
+    Skip(PathBuf),
+    /// 合并到已存在的同名文件夹
+    Merge(PathBuf),
 }
 
-/// 递归复制目录
-fn copy_dir_all(src: &Path, dst: &Path) -> Result<()> {
-    fs::create_dir_all(dst)
-        .with_context(|| format!("创建目标目录失败: {:?}", dst))?;
-    
-    for entry in fs::read_dir(src)
-        .with_context(|| format!("读取源目录失败: {:?}", src))? {
-        let entry = entry?;
-        let file_type = entry.file_type()?;
-        let src_path = entry.path();
-        let dst_path = dst.join(entry.file_name());
-        
-        if file_type.is_dir() {
-            copy_dir_all(&src_path, &dst_path)?;
-        } else {
-            fs::copy(&src_path, &dst_path)
-                .with_context(|| format!("复制文件失败: {:?} -> {:?}", src_path, dst_path))?;
-        }
-    }
-    Ok(())
+/// 根据冲突策略确定目标路径（会创建目标目录）
+fn resolve_target(source: &Path, dest_dir: &str, policy: &ConflictPolicy) -> Result<Resolution> {
+    // 获取文件名
+    let file_name = source
+        .file_name()
+        .context("无法获取文件名")?;
+
+    resolve_target_named(source, file_name, dest_dir, policy)
 }
 
-/// 根据冲突策略移动文件
-fn move_file_with_strategy(source: &Path, dest_dir: &str, strategy: &ConflictStrategy) -> Result<String> {
+/// 根据冲突策略确定指定文件名的目标路径（会创建目标目录）
+///
+/// `source` 用于比较修改时间、大小和内容；策略为询问且目标已存在时返回
+/// [`conflict::ConflictPending`] 错误。
+pub(crate) fn resolve_target_named(source: &Path, file_name: &OsStr, dest_dir: &str, policy: &ConflictPolicy) -> Result<Resolution> {
     let dest_path = PathBuf::from(dest_dir);
     
     // 创建目标目录
     fs::create_dir_all(&dest_path)
         .with_context(|| format!("创建目录失败: {:?}", dest_path))?;
 
-    // 获取文件名
-    let file_name = source
-        .file_name()
-        .context("无法获取文件名")?;
-
     let mut final_dest = dest_path.join(file_name);
 
-    // 检查文件是否已存在
-    if final_dest.exists() {
-        match strategy {
+    // 检查文件是否已存在（包括失效的符号链接）
+    if final_dest.symlink_metadata().is_ok() {
+        match &policy.strategy {
             ConflictStrategy::Skip => {
                 info!("目标文件已存在，跳过: {:?}", final_dest);
-                return Ok(final_dest.to_string_lossy().to_string());
+                return Ok(Resolution::Skip(final_dest));
             }
             ConflictStrategy::Overwrite => {
                 info!("目标文件已存在，将覆盖: {:?}", final_dest);
@@ -205,112 +878,397 @@ fn move_file_with_strategy(source: &Path, dest_dir: &str, strategy: &ConflictStr
             }
             ConflictStrategy::Rename => {
                 // 生成副本文件名
-                final_dest = generate_copy_name(&final_dest)?;
+                final_dest = generate_copy_name(&final_dest, &policy.copy_name)?;
                 info!("目标文件已存在，重命名为: {:?}", final_dest);
             }
+            ConflictStrategy::KeepNewer => {
+                if !conflict::is_newer(source, &final_dest) {
+                    info!("目标文件较新，跳过: {:?}", final_dest);
+                    return Ok(Resolution::Skip(final_dest));
+                }
+                info!("源文件较新，将覆盖: {:?}", final_dest);
+            }
+            ConflictStrategy::KeepLarger => {
+                if !conflict::is_larger(source, &final_dest) {
+                    info!("目标文件较大，跳过: {:?}", final_dest);
+                    return Ok(Resolution::Skip(final_dest));
+                }
+                info!("源文件较大，将覆盖: {:?}", final_dest);
+            }
+            ConflictStrategy::SkipIfIdentical => {
+                if conflict::is_identical(source, &final_dest)? {
+                    info!("目标文件内容相同，跳过: {:?}", final_dest);
+                    return Ok(Resolution::Skip(final_dest));
+                }
+                final_dest = generate_copy_name(&final_dest, &policy.copy_name)?;
+                info!("目标文件内容不同，重命名为: {:?}", final_dest);
+            }
+            ConflictStrategy::AppendTimestamp => {
+                let (stem, extension) = split_name(&final_dest)?;
+                let stamped = dest_path.join(conflict::timestamped_name(stem, extension, Local::now()));
+                // 同一分钟内再次冲突时使用副本名称
+                final_dest = if stamped.symlink_metadata().is_ok() { generate_copy_name(&stamped, &policy.copy_name)? } else { stamped };
+                info!("目标文件已存在，添加时间后缀: {:?}", final_dest);
+            }
+            ConflictStrategy::Versioned => {
+                let (stem, extension) = split_name(&final_dest)?;
+                final_dest = dest_path.join(conflict::versioned_name(&dest_path, stem, extension)?);
+                info!("目标文件已存在，保存为新版本: {:?}", final_dest);
+            }
+            ConflictStrategy::Ask => {
+                return Err(conflict::ConflictPending { target: final_dest }.into());
+            }
+            ConflictStrategy::Merge => {
+                let is_dir = |path: &Path| path.symlink_metadata().is_ok_and(|m| m.is_dir());
+                if is_dir(source) && is_dir(&final_dest) {
+                    info!("目标文件夹已存在，将合并: {:?}", final_dest);
+                    return Ok(Resolution::Merge(final_dest));
+                }
+                // 不是两个文件夹时按同名文件的策略处理
+                return resolve_target_named(source, file_name, dest_dir, &policy.for_merged_files());
+            }
         }
     }
 
-    // 移动文件或文件夹
-    fs::rename(source, &final_dest)
-        .or_else(|_| -> Result<()> {
-            // 如果跨分区移动失败，则先复制再删除
-            if source.is_dir() {
-                // 对于目录，需要递归复制
-                copy_dir_all(source, &final_dest)?;
-                fs::remove_dir_all(source)?;
-            } else {
-                // 对于文件，直接复制
-                fs::copy(source, &final_dest)?;
-                fs::remove_file(source)?;
+    Ok(Resolution::Proceed(final_dest))
+}
+
+/// 按冲突策略重命名，结果写入 `entry` 用于撤销
+///
+/// 新名称与原名称相同时不做任何操作；只有大小写不同且目标就是文件本身时
+/// （不区分大小写的文件系统）直接重命名，其他情况与移动一样按冲突策略处理。
+fn rename_with_policy(source: &Path, new_path: &Path, policy: &ConflictPolicy, entry: &mut HistoryEntry) -> Result<PathBuf> {
+    let new_name = new_path.file_name().context("无法获取新文件名")?;
+    let dest_dir = new_path.parent().context("无法获取目标目录")?;
+
+    if new_path == source {
+        debug!("名称未改变，跳过重命名: {:?}", source);
+        entry.rename = Some(RenameOutcome::Unchanged);
+        entry.status = HistoryStatus::Skipped;
+        return Ok(source.to_path_buf());
+    }
+
+    let case_only = Some(dest_dir) == source.parent()
+        && source.file_name().is_some_and(|name| name.to_string_lossy().to_lowercase() == new_name.to_string_lossy().to_lowercase())
+        && (new_path.symlink_metadata().is_err() || is_same_entry(source, new_path));
+    let (final_dest, outcome) = if case_only {
+        (new_path.to_path_buf(), RenameOutcome::CaseOnly)
+    } else {
+        match resolve_target_named(source, new_name, &dest_dir.to_string_lossy(), policy)? {
+            Resolution::Skip(_) => {
+                entry.rename = Some(RenameOutcome::Skipped);
+                entry.status = HistoryStatus::Skipped;
+                return Ok(source.to_path_buf());
+            }
+            Resolution::Merge(path) => {
+                entry.merge = merge_dir(source, &path, policy, None, TransferKind::Move)?;
+                entry.rename = Some(RenameOutcome::Merged);
+                return Ok(path);
             }
-            Ok(())
-        })
-        .with_context(|| format!("移动失败: {:?} -> {:?}", source, final_dest))?;
+            Resolution::Proceed(path) if path.symlink_metadata().is_ok() => (path, RenameOutcome::Replaced),
+            Resolution::Proceed(path) if path != new_path => (path, RenameOutcome::KeptBoth),
+            Resolution::Proceed(path) => (path, RenameOutcome::Renamed),
+        }
+    };
 
-    if source.is_dir() {
+    fs::rename(source, &final_dest)
+        .with_context(|| format!("重命名文件失败: {:?} -> {:?}", source, final_dest))?;
+    entry.rename = Some(outcome);
+    info!("文件已重命名 ({:?}): {:?} -> {:?}", outcome, source, final_dest);
+    Ok(final_dest)
+}
+
+/// 两个路径是否指向同一个文件（不区分大小写的文件系统上名称只有大小写不同时）
+fn is_same_entry(a: &Path, b: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        match (a.symlink_metadata(), b.symlink_metadata()) {
+            (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+            _ => false,
+        }
+    }
+    // Windows 默认不区分大小写，两个路径都存在时视为同一个文件
+    #[cfg(not(unix))]
+    {
+        a.symlink_metadata().is_ok() && b.symlink_metadata().is_ok()
+    }
+}
+
+/// 移动结果
+pub(crate) struct MoveOutcome {
+    /// 实际的目标路径
+    pub path: String,
+    /// 复制时的 SHA-256 校验值（同分区重命名或跳过时为 None）
+    pub checksum: Option<String>,
+    /// 合并文件夹时每一项的结果
+    pub merged: Vec<MergeEntry>,
+}
+
+impl MoveOutcome {
+    fn new(path: &Path, checksum: Option<String>) -> Self {
+        Self { path: path.to_string_lossy().to_string(), checksum, merged: Vec::new() }
+    }
+}
+
+/// 根据冲突策略移动文件
+pub(crate) fn move_file_with_strategy(source: &Path, dest_dir: &str, policy: &ConflictPolicy) -> Result<String> {
+    move_file_checked(source, dest_dir, policy, None).map(|outcome| outcome.path)
+}
+
+/// 根据冲突策略移动文件，跨分区时校验复制结果
+///
+/// 指定 `ctx` 时跨分区复制会发送进度并可以被取消（取消后源文件保持不变），
+/// 并按整理设置保留元数据；未指定时保留全部元数据。
+pub(crate) fn move_file_checked(source: &Path, dest_dir: &str, policy: &ConflictPolicy, ctx: Option<&OrganizeContext>) -> Result<MoveOutcome> {
+    match resolve_target(source, dest_dir, policy)? {
+        Resolution::Proceed(path) => move_to(source, &path, ctx),
+        Resolution::Skip(path) => Ok(MoveOutcome::new(&path, None)),
+        Resolution::Merge(path) => {
+            let merged = merge_dir(source, &path, policy, ctx, TransferKind::Move)?;
+            Ok(MoveOutcome { merged, ..MoveOutcome::new(&path, None) })
+        }
+    }
+}
+
+/// 移动到已确定的目标路径；跨分区重命名失败时先复制并校验，一致后再删除源文件
+fn move_to(source: &Path, final_dest: &Path, ctx: Option<&OrganizeContext>) -> Result<MoveOutcome> {
+    let checksum = match fs::rename(source, final_dest) {
+        Ok(()) => None,
+        Err(e) => {
+            debug!("重命名失败（{}），改为复制后删除: {:?}", e, source);
+            let (preserve, progress) = match ctx {
+                Some(ctx) => (ctx.preserve, ctx.operations.start(source)),
+                None => (PreserveMetadata::default(), Progress::detached()),
+            };
+            let checksum = transfer::move_verified(source, final_dest, &preserve, &progress)
+                .with_context(|| format!("移动失败: {:?} -> {:?}", source, final_dest))?;
+            Some(checksum)
+        }
+    };
+
+    if final_dest.is_dir() {
         info!("文件夹已移动: {:?} -> {:?}", source, final_dest);
     } else {
         info!("文件已移动: {:?} -> {:?}", source, final_dest);
     }
-    Ok(final_dest.to_string_lossy().to_string())
+    Ok(MoveOutcome::new(final_dest, checksum))
 }
 
-/// 根据冲突策略复制文件
-fn copy_file_with_strategy(source: &Path, dest_dir: &str, strategy: &ConflictStrategy) -> Result<String> {
-    let dest_path = PathBuf::from(dest_dir);
-    
-    // 创建目标目录
-    fs::create_dir_all(&dest_path)
-        .with_context(|| format!("创建目录失败: {:?}", dest_path))?;
+/// 根据冲突策略复制文件或文件夹（分块复制并校验，发送进度，可取消）
+fn copy_file_with_strategy(source: &Path, dest_dir: &str, policy: &ConflictPolicy, ctx: &OrganizeContext) -> Result<MoveOutcome> {
+    match resolve_target(source, dest_dir, policy)? {
+        Resolution::Proceed(path) => copy_to(source, &path, Some(ctx)),
+        Resolution::Skip(path) => Ok(MoveOutcome::new(&path, None)),
+        Resolution::Merge(path) => {
+            let merged = merge_dir(source, &path, policy, Some(ctx), TransferKind::Copy)?;
+            Ok(MoveOutcome { merged, ..MoveOutcome::new(&path, None) })
+        }
+    }
+}
 
-    // 获取文件名
-    let file_name = source
-        .file_name()
-        .context("无法获取文件名")?;
+/// 复制到已确定的目标路径（取消或失败时已复制的部分会被清理）
+fn copy_to(source: &Path, final_dest: &Path, ctx: Option<&OrganizeContext>) -> Result<MoveOutcome> {
+    let (preserve, progress) = match ctx {
+        Some(ctx) => (ctx.preserve, ctx.operations.start(source)),
+        None => (PreserveMetadata::default(), Progress::detached()),
+    };
+    let checksum = if source.is_dir() {
+        transfer::copy_dir_verified(source, final_dest, &preserve, &progress)
+    } else {
+        transfer::copy_file_verified(source, final_dest, &preserve, &progress)
+    }
+    .with_context(|| format!("复制失败: {:?} -> {:?}", source, final_dest))?;
 
-    let mut final_dest = dest_path.join(file_name);
+    info!("文件已复制: {:?} -> {:?}", source, final_dest);
+    Ok(MoveOutcome::new(final_dest, Some(checksum)))
+}
 
-    // 检查文件是否已存在
-    if final_dest.exists() {
-        match strategy {
-            ConflictStrategy::Skip => {
-                info!("目标文件已存在，跳过: {:?}", final_dest);
-                return Ok(final_dest.to_string_lossy().to_string());
-            }
-            ConflictStrategy::Overwrite => {
-                info!("目标文件已存在，将覆盖: {:?}", final_dest);
-                // 继续执行，会覆盖
-            }
-            ConflictStrategy::Rename => {
-                // 生成副本文件名
-                final_dest = generate_copy_name(&final_dest)?;
-                info!("目标文件已存在，重命名为: {:?}", final_dest);
+/// 将文件夹合并到已存在的同名文件夹，返回每一项的结果
+///
+/// 两边都有的子文件夹继续合并，其余各项按同名文件的策略移动或复制，
+/// 单项失败不影响其他项。移动时源文件夹中没有剩余内容的会被删除。
+fn merge_dir(source: &Path, dest: &Path, policy: &ConflictPolicy, ctx: Option<&OrganizeContext>, kind: TransferKind) -> Result<Vec<MergeEntry>> {
+    let files = policy.for_merged_files();
+    let mut entries = Vec::new();
+    merge_into(source, dest, Path::new(""), &files, ctx, kind, &mut entries)?;
+
+    let failed = entries.iter().filter(|e| matches!(e.outcome, MergeOutcome::Failed { .. })).count();
+    if failed > 0 {
+        warn!("文件夹已合并，{} 项失败: {:?} -> {:?}", failed, source, dest);
+    } else {
+        info!("文件夹已合并（{} 项）: {:?} -> {:?}", entries.len(), source, dest);
+    }
+    Ok(entries)
+}
+
+fn merge_into(source: &Path, dest: &Path, relative: &Path, policy: &ConflictPolicy, ctx: Option<&OrganizeContext>, kind: TransferKind, entries: &mut Vec<MergeEntry>) -> Result<()> {
+    for item in fs::read_dir(source).with_context(|| format!("无法读取文件夹: {:?}", source))? {
+        let item = item?;
+        let child = item.path();
+        let target = dest.join(item.file_name());
+        let relative = relative.join(item.file_name());
+
+        // 不跟随符号链接
+        let both_dirs = item.file_type()?.is_dir() && target.symlink_metadata().is_ok_and(|m| m.is_dir());
+        let result = if both_dirs {
+            merge_into(&child, &target, &relative, policy, ctx, kind, entries).map(|_| None)
+        } else {
+            merge_entry(&child, dest, &target, policy, ctx, kind).map(Some)
+        };
+
+        let outcome = match result {
+            Ok(Some(outcome)) => outcome,
+            Ok(None) => continue,
+            Err(e) => {
+                warn!("合并失败: {:?}: {:#}", child, e);
+                MergeOutcome::Failed { error: format!("{:#}", e) }
             }
-        }
+        };
+        entries.push(MergeEntry { path: relative.to_string_lossy().to_string(), outcome });
     }
 
-    // 复制文件
-    fs::copy(source, &final_dest)
-        .with_context(|| format!("复制文件失败: {:?} -> {:?}", source, final_dest))?;
+    // 有被跳过或失败的项时源文件夹不为空，保留
+    if kind == TransferKind::Move && fs::remove_dir(source).is_err() {
+        debug!("源文件夹中仍有内容，保留: {:?}", source);
+    }
+    Ok(())
+}
 
-    info!("文件已复制: {:?} -> {:?}", source, final_dest);
+/// 合并时处理一项（文件，或目标中没有的文件夹）
+fn merge_entry(source: &Path, dest_dir: &Path, target: &Path, policy: &ConflictPolicy, ctx: Option<&OrganizeContext>, kind: TransferKind) -> Result<MergeOutcome> {
+    let existed = target.symlink_metadata().is_ok();
+    let name = source.file_name().context("无法获取文件名")?;
+    let final_dest = match resolve_target_named(source, name, &dest_dir.to_string_lossy(), policy)? {
+        Resolution::Proceed(path) => path,
+        Resolution::Skip(_) | Resolution::Merge(_) => return Ok(MergeOutcome::Skipped),
+    };
+
+    match kind {
+        TransferKind::Move => move_to(source, &final_dest, ctx)?,
+        TransferKind::Copy => copy_to(source, &final_dest, ctx)?,
+    };
+    Ok(if final_dest != target {
+        MergeOutcome::KeptBoth { name: final_dest.file_name().unwrap_or_default().to_string_lossy().to_string() }
+    } else if existed {
+        MergeOutcome::Replaced
+    } else {
+        MergeOutcome::Added
+    })
+}
+
+/// 根据冲突策略在目标目录中创建链接（`hard` 为 true 时创建硬链接）
+fn link_file_with_strategy(source: &Path, dest_dir: &str, policy: &ConflictPolicy, hard: bool) -> Result<String> {
+    let final_dest = match resolve_target(source, dest_dir, policy)? {
+        Resolution::Proceed(path) => path,
+        Resolution::Skip(path) => return Ok(path.to_string_lossy().to_string()),
+        Resolution::Merge(path) => {
+            info!("链接无法合并到已存在的文件夹，跳过: {:?}", path);
+            return Ok(path.to_string_lossy().to_string());
+        }
+    };
+
+    // 覆盖时先移除已有的目标，创建链接不会自动替换
+    if let Ok(metadata) = final_dest.symlink_metadata() {
+        if metadata.is_dir() {
+            fs::remove_dir_all(&final_dest)?;
+        } else {
+            fs::remove_file(&final_dest)?;
+        }
+    }
+
+    if hard {
+        if source.is_dir() {
+            anyhow::bail!("无法为文件夹创建硬链接: {:?}", source);
+        }
+        fs::hard_link(source, &final_dest)
+            .with_context(|| format!("创建硬链接失败（需位于同一分区）: {:?} -> {:?}", source, final_dest))?;
+        info!("已创建硬链接: {:?} -> {:?}", final_dest, source);
+    } else {
+        let target = source
+            .canonicalize()
+            .with_context(|| format!("无法获取文件的完整路径: {:?}", source))?;
+        create_symlink(&target, &final_dest)?;
+        info!("已创建符号链接: {:?} -> {:?}", final_dest, target);
+    }
     Ok(final_dest.to_string_lossy().to_string())
 }
 
-/// 生成副本文件名（例如：file.txt -> file (副本).txt，file (副本).txt -> file (副本 2).txt）
-fn generate_copy_name(path: &Path) -> Result<PathBuf> {
+/// 创建指向 `target` 的符号链接
+fn create_symlink(target: &Path, link: &Path) -> Result<()> {
+    #[cfg(unix)]
+    let result = std::os::unix::fs::symlink(target, link);
+    
+    // Windows 区分文件和文件夹链接，且可能需要开发者模式或管理员权限
+    #[cfg(windows)]
+    let result = if target.is_dir() {
+        std::os::windows::fs::symlink_dir(target, link)
+    } else {
+        std::os::windows::fs::symlink_file(target, link)
+    };
+
+    result.with_context(|| format!("创建符号链接失败: {:?} -> {:?}", link, target))
+}
+
+/// 路径本身是否为符号链接
+fn is_symlink(path: &Path) -> bool {
+    path.symlink_metadata()
+        .map(|m| m.file_type().is_symlink())
+        .unwrap_or(false)
+}
+
+/// 按副本名称格式生成第一个未被占用的副本文件名（例如：file.txt -> file (副本 1).txt）
+fn generate_copy_name(path: &Path, format: &CopyNameFormat) -> Result<PathBuf> {
     let parent = path.parent().context("无法获取父目录")?;
+    let (stem, extension) = split_name(path)?;
+
+    // 读取一次目录即可找到可用的序号
+    let names: Vec<String> = fs::read_dir(parent)
+        .with_context(|| format!("无法读取目录: {:?}", parent))?
+        .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+        .collect();
+    Ok(parent.join(format.next_free(stem, extension, names.iter().map(String::as_str))))
+}
+
+/// 拆分主文件名和扩展名（没有扩展名时为空字符串）
+fn split_name(path: &Path) -> Result<(&str, &str)> {
     let stem = path.file_stem()
         .and_then(|s| s.to_str())
         .context("无法获取文件名")?;
     let extension = path.extension()
         .and_then(|e| e.to_str())
         .unwrap_or("");
+    Ok((stem, extension))
+}
 
-    // 尝试找到可用的副本文件名
-    for i in 1..1000 {
-        let new_name = if i == 1 {
-            if extension.is_empty() {
-                format!("{} (副本)", stem)
-            } else {
-                format!("{} (副本).{}", stem, extension)
-            }
-        } else {
-            if extension.is_empty() {
-                format!("{} (副本 {})", stem, i)
-            } else {
-                format!("{} (副本 {}).{}", stem, i, extension)
-            }
-        };
-
-        let new_path = parent.join(&new_name);
-        if !new_path.exists() {
-            return Ok(new_path);
-        }
-    }
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    anyhow::bail!("无法生成可用的副本文件名")
-}
+    #[test]
+    fn test_skipped_rename_stops_pipeline() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        fs::write(dir.join("a.txt"), "new").unwrap();
+        fs::write(dir.join("b.txt"), "old").unwrap();
 
+        let rule: Rule = serde_json::from_value(serde_json::json!({
+            "id": "r",
+            "name": "r",
+            "enabled": true,
+            "conditions": [{ "type": "Extension", "values": ["txt"] }],
+            "actions": [
+                { "type": "Rename", "pattern": "b.{ext}" },
+                { "type": "MoveTo", "destination": dir.join("out").to_string_lossy() }
+            ],
+            "priority": 0,
+            "conflict_strategy": "skip"
+        }))
+        .unwrap();
 
+        organize_single_file(&dir.join("a.txt").to_string_lossy(), &[rule], &OrganizeContext::default()).unwrap();
+        assert_eq!(fs::read_to_string(dir.join("a.txt")).unwrap(), "new");
+        assert_eq!(fs::read_to_string(dir.join("b.txt")).unwrap(), "old");
+        assert!(!dir.join("out").exists());
+    }
+}
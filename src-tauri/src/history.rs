@@ -0,0 +1,248 @@
+use anyhow::{Context, Result};
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use tracing::{info, warn};
+
+/// 运行历史最多保留的记录数
+const MAX_ENTRIES: usize = 5000;
+
+/// 同一毫秒内生成多个 ID 时使用的序号
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// 步骤执行状态
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum HistoryStatus {
+    Success,
+    Skipped,
+    Failed,
+}
+
+/// 重命名的结果（撤销时据此判断能否改回原名称）
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RenameOutcome {
+    Renamed,
+    /// 只改变了大小写
+    CaseOnly,
+    /// 新名称与原名称相同，没有改动
+    Unchanged,
+    /// 目标已存在，按冲突策略跳过
+    Skipped,
+    /// 目标已存在，按冲突策略使用了其他名称
+    KeptBoth,
+    /// 覆盖了已存在的同名文件（被覆盖的文件无法通过撤销恢复）
+    Replaced,
+    /// 文件夹合并到了已存在的同名文件夹（各项结果见 merge）
+    Merged,
+}
+
+/// 合并文件夹时一项的处理结果
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum MergeOutcome {
+    /// 目标中原来没有，已移动或复制
+    Added,
+    Replaced,
+    Skipped,
+    /// 目标已存在，使用了其他名称
+    KeptBoth { name: String },
+    Failed { error: String },
+}
+
+/// 合并文件夹时的一项
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MergeEntry {
+    /// 相对于合并的文件夹的路径
+    pub path: String,
+    #[serde(flatten)]
+    pub outcome: MergeOutcome,
+}
+
+/// 外部命令的执行结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CommandOutput {
+    pub program: String,
+    pub args: Vec<String>,
+    /// 退出码（超时或被信号终止时为 None）
+    pub exit_code: Option<i32>,
+    #[serde(default)]
+    pub timed_out: bool,
+    pub stdout: String,
+    pub stderr: String,
+    pub duration_ms: u64,
+}
+
+/// 运行历史记录（每条对应流水线中的一个步骤）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub id: String,
+    pub timestamp: String,
+    pub rule_name: String,
+    /// 动作类型（与规则配置中的 type 相同）
+    pub action: String,
+    pub source: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+    pub status: HistoryStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<CommandOutput>,
+    /// 跨分区移动时校验的 SHA-256 值
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checksum: Option<String>,
+    /// 重命名和清理文件名的结果
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rename: Option<RenameOutcome>,
+    /// 合并文件夹时每一项的结果
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub merge: Vec<MergeEntry>,
+}
+
+impl HistoryEntry {
+    /// 创建当前时间的记录
+    pub fn new(rule_name: &str, action: &str, source: &str, status: HistoryStatus) -> Self {
+        let now = Local::now();
+        Self {
+            id: format!("{}-{}", now.format("%Y%m%d%H%M%S%3f"), NEXT_ID.fetch_add(1, Ordering::Relaxed)),
+            timestamp: now.to_rfc3339(),
+            rule_name: rule_name.to_string(),
+            action: action.to_string(),
+            source: source.to_string(),
+            output: None,
+            status,
+            message: None,
+            command: None,
+            checksum: None,
+            rename: None,
+            merge: Vec::new(),
+        }
+    }
+
+    /// 按合并文件夹各项的结果设置状态，返回是否有项被合并
+    ///
+    /// 没有任何项被合并时为跳过（有失败项时为失败）；部分项被跳过或失败时
+    /// 仍为成功，并在消息中说明。
+    pub fn apply_merge_status(&mut self) -> bool {
+        let skipped = self.merge.iter().filter(|e| e.outcome == MergeOutcome::Skipped).count();
+        let failed = self.merge.iter().filter(|e| matches!(e.outcome, MergeOutcome::Failed { .. })).count();
+        let merged = self.merge.len() - skipped - failed;
+
+        if merged == 0 {
+            self.status = if failed > 0 { HistoryStatus::Failed } else { HistoryStatus::Skipped };
+        } else if skipped + failed > 0 {
+            self.message = Some(format!("部分合并：{} 项完成，{} 项跳过，{} 项失败", merged, skipped, failed));
+        }
+        merged > 0
+    }
+}
+
+/// 运行历史（每行一条 JSON 记录）
+///
+/// 未指定文件路径时不记录（用于预览和测试）。
+#[derive(Debug, Default)]
+pub struct RunHistory {
+    path: Option<PathBuf>,
+    lock: Mutex<()>,
+}
+
+impl RunHistory {
+    /// 打开运行历史文件，超出保留数量的旧记录会被清理
+    pub fn open<P: AsRef<Path>>(path: P) -> Self {
+        let history = Self {
+            path: Some(path.as_ref().to_path_buf()),
+            lock: Mutex::new(()),
+        };
+        if let Err(e) = history.compact() {
+            warn!("整理运行历史失败: {}", e);
+        }
+        history
+    }
+
+    /// 追加一条记录（写入失败只记录日志，不影响整理流程）
+    pub fn record(&self, entry: &HistoryEntry) {
+        let path = match &self.path {
+            Some(p) => p,
+            None => return,
+        };
+        let _guard = self.lock.lock();
+
+        let result = (|| -> Result<()> {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+            writeln!(file, "{}", serde_json::to_string(entry)?)?;
+            Ok(())
+        })();
+
+        if let Err(e) = result {
+            warn!("写入运行历史失败: {}", e);
+        }
+    }
+
+    /// 读取最近的记录（最新的在前）
+    pub fn list(&self, limit: usize) -> Result<Vec<HistoryEntry>> {
+        let _guard = self.lock.lock();
+        let mut entries = self.read_all()?;
+        entries.reverse();
+        entries.truncate(limit);
+        Ok(entries)
+    }
+
+    /// 清空运行历史
+    pub fn clear(&self) -> Result<()> {
+        let path = match &self.path {
+            Some(p) => p,
+            None => return Ok(()),
+        };
+        let _guard = self.lock.lock();
+        if path.exists() {
+            fs::remove_file(path).with_context(|| format!("无法删除运行历史: {:?}", path))?;
+        }
+        info!("运行历史已清空");
+        Ok(())
+    }
+
+    fn read_all(&self) -> Result<Vec<HistoryEntry>> {
+        let path = match &self.path {
+            Some(p) if p.exists() => p,
+            _ => return Ok(Vec::new()),
+        };
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("无法读取运行历史: {:?}", path))?;
+
+        // 跳过无法解析的行（例如写入中断留下的半行）
+        Ok(content
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect())
+    }
+
+    /// 只保留最近的记录
+    fn compact(&self) -> Result<()> {
+        let path = match &self.path {
+            Some(p) => p,
+            None => return Ok(()),
+        };
+        let entries = self.read_all()?;
+        if entries.len() <= MAX_ENTRIES {
+            return Ok(());
+        }
+
+        let content: String = entries[entries.len() - MAX_ENTRIES..]
+            .iter()
+            .filter_map(|e| serde_json::to_string(e).ok())
+            .map(|line| line + "\n")
+            .collect();
+        fs::write(path, content).with_context(|| format!("无法写入运行历史: {:?}", path))?;
+        info!("运行历史已清理，保留最近 {} 条记录", MAX_ENTRIES);
+        Ok(())
+    }
+}
@@ -0,0 +1,184 @@
+use crate::file_ops::{self, Resolution};
+use crate::conflict::ConflictPolicy;
+use anyhow::{Context, Result};
+use image::codecs::webp::WebPEncoder;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageOutputFormat};
+use std::ffi::OsStr;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use tracing::info;
+
+/// 支持输出的图片格式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    Png,
+    Jpeg,
+    WebP,
+    Bmp,
+    Tiff,
+    Gif,
+}
+
+impl OutputFormat {
+    /// 解析格式名称（不区分大小写）
+    pub fn parse(name: &str) -> Result<Self> {
+        match name.to_lowercase().as_str() {
+            "png" => Ok(OutputFormat::Png),
+            "jpg" | "jpeg" => Ok(OutputFormat::Jpeg),
+            "webp" => Ok(OutputFormat::WebP),
+            "bmp" => Ok(OutputFormat::Bmp),
+            "tif" | "tiff" => Ok(OutputFormat::Tiff),
+            "gif" => Ok(OutputFormat::Gif),
+            _ => anyhow::bail!("不支持的图片格式: {}", name),
+        }
+    }
+
+    /// 输出文件的扩展名
+    pub fn extension(&self) -> &'static str {
+        match self {
+            OutputFormat::Png => "png",
+            OutputFormat::Jpeg => "jpg",
+            OutputFormat::WebP => "webp",
+            OutputFormat::Bmp => "bmp",
+            OutputFormat::Tiff => "tiff",
+            OutputFormat::Gif => "gif",
+        }
+    }
+}
+
+/// 未设置时的 JPEG 质量
+const DEFAULT_JPEG_QUALITY: u8 = 85;
+
+/// 图片转换选项
+#[derive(Debug, Clone)]
+pub struct ConvertOptions {
+    pub format: OutputFormat,
+    /// 长边最大像素（超过时等比缩小，不放大）
+    pub max_dimension: Option<u32>,
+    /// JPEG 质量（1-100）
+    pub quality: u8,
+}
+
+impl ConvertOptions {
+    /// 根据动作参数创建转换选项，拒绝无法满足的设置
+    ///
+    /// 重新编码无法保留 EXIF、ICC 等元数据，因此 `strip_metadata` 必须为 true；
+    /// 只有 JPEG 是有损编码，其他格式设置质量时报错而不是忽略。
+    pub fn new(format: &str, max_dimension: Option<u32>, quality: Option<u8>, strip_metadata: bool) -> Result<Self> {
+        let format = OutputFormat::parse(format)?;
+        if !strip_metadata {
+            anyhow::bail!("暂不支持保留图片元数据（转换会重新编码图片），请将 strip_metadata 设为 true");
+        }
+        if quality.is_some() && format != OutputFormat::Jpeg {
+            anyhow::bail!("只有 JPEG 支持设置质量，{} 使用无损编码", format.extension());
+        }
+        Ok(Self {
+            format,
+            max_dimension,
+            quality: quality.unwrap_or(DEFAULT_JPEG_QUALITY),
+        })
+    }
+}
+
+/// 转换后的文件名（原文件名 + 新扩展名）
+pub fn output_name(file_name: &str, format: OutputFormat) -> String {
+    let stem = Path::new(file_name)
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or(file_name);
+    format!("{}.{}", stem, format.extension())
+}
+
+/// 转换图片并写入目标目录，返回输出文件路径（目标已存在且策略为跳过时返回 None）
+///
+/// 图片经过解码和重新编码，EXIF 等元数据不会写入输出文件。
+/// 先写入临时文件再替换，目标与原图相同时也不会损坏原图。
+pub fn convert(source: &Path, dest_dir: &str, options: &ConvertOptions, policy: &ConflictPolicy) -> Result<Option<String>> {
+    let file_name = source
+        .file_name()
+        .and_then(|n| n.to_str())
+        .context("无法获取文件名")?;
+    let name = output_name(file_name, options.format);
+
+    let final_dest = match file_ops::resolve_target_named(source, OsStr::new(&name), dest_dir, policy)? {
+        Resolution::Proceed(path) => path,
+        Resolution::Skip(_) | Resolution::Merge(_) => return Ok(None),
+    };
+
+    let mut image = image::io::Reader::open(source)
+        .with_context(|| format!("无法打开图片: {:?}", source))?
+        .with_guessed_format()?
+        .decode()
+        .with_context(|| format!("无法解码图片: {:?}", source))?;
+
+    if let Some(max) = options.max_dimension.filter(|m| *m > 0) {
+        if image.width() > max || image.height() > max {
+            // resize 会保持宽高比，使长边等于 max
+            image = image.resize(max, max, FilterType::Lanczos3);
+        }
+    }
+
+    let temp = final_dest.with_file_name(format!(".{}.converting", name));
+    let result = encode(&image, &temp, options);
+    if let Err(e) = result {
+        let _ = fs::remove_file(&temp);
+        return Err(e.context(format!("图片编码失败: {:?}", source)));
+    }
+    fs::rename(&temp, &final_dest)
+        .with_context(|| format!("写入图片失败: {:?}", final_dest))?;
+
+    info!("图片已转换: {:?} -> {:?} ({}x{})", source, final_dest, image.width(), image.height());
+    Ok(Some(final_dest.to_string_lossy().to_string()))
+}
+
+/// 按目标格式编码并写入文件
+fn encode(image: &DynamicImage, path: &Path, options: &ConvertOptions) -> Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+
+    match options.format {
+        // JPEG 不支持透明通道
+        OutputFormat::Jpeg => DynamicImage::ImageRgb8(image.to_rgb8())
+            .write_to(&mut writer, ImageOutputFormat::Jpeg(options.quality.clamp(1, 100)))?,
+        // 无损 WebP 编码只支持 8 位 RGB(A)
+        OutputFormat::WebP => DynamicImage::ImageRgba8(image.to_rgba8())
+            .write_with_encoder(WebPEncoder::new_lossless(&mut writer))?,
+        OutputFormat::Png => image.write_to(&mut writer, ImageOutputFormat::Png)?,
+        OutputFormat::Bmp => image.write_to(&mut writer, ImageOutputFormat::Bmp)?,
+        OutputFormat::Tiff => image.write_to(&mut writer, ImageOutputFormat::Tiff)?,
+        OutputFormat::Gif => DynamicImage::ImageRgba8(image.to_rgba8())
+            .write_to(&mut writer, ImageOutputFormat::Gif)?,
+    }
+
+    writer.flush()?;
+    writer.get_ref().sync_all()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbImage;
+    use crate::models::ConflictStrategy;
+
+    #[test]
+    fn test_convert_and_downscale() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let source = dir.join("scan.bmp");
+        RgbImage::new(40, 20).save(&source).unwrap();
+
+        let options = ConvertOptions::new("PNG", Some(10), None, true).unwrap();
+        let dest = dir.join("out");
+        let output = convert(&source, &dest.to_string_lossy(), &options, &ConflictStrategy::Rename.into()).unwrap().unwrap();
+
+        assert_eq!(Path::new(&output), dest.join("scan.png"));
+        assert_eq!(image::image_dimensions(&output).unwrap(), (10, 5));
+        assert!(convert(&source, &dest.to_string_lossy(), &options, &ConflictStrategy::Skip.into()).unwrap().is_none());
+        assert!(OutputFormat::parse("heic").is_err());
+        assert!(ConvertOptions::new("webp", None, Some(80), true).is_err());
+        assert!(ConvertOptions::new("jpg", None, None, false).is_err());
+        assert_eq!(ConvertOptions::new("jpg", None, Some(70), true).unwrap().quality, 70);
+    }
+}
@@ -0,0 +1,270 @@
+use crate::transfer;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local, Utc};
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use tracing::{info, warn};
+
+/// 全局意图日志（启动时初始化，未初始化时不记录）
+static JOURNAL: OnceCell<Journal> = OnceCell::new();
+
+/// 同一毫秒内生成多个 ID 时使用的序号
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// 操作类型
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TransferKind {
+    Copy,
+    /// 复制完成后删除源文件
+    Move,
+}
+
+/// 操作进行到的阶段
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Phase {
+    /// 正在写入暂存路径，目标位置尚未改变
+    Staging,
+    /// 暂存内容已校验，正在放到目标位置（移动时随后删除源文件）
+    Committing,
+}
+
+/// 一个未完成操作的意图记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Intent {
+    pub id: String,
+    pub kind: TransferKind,
+    pub source: String,
+    pub dest: String,
+    /// 目标目录中的暂存路径
+    pub staging: String,
+    pub phase: Phase,
+    pub started_at: DateTime<Utc>,
+}
+
+/// 启动恢复时对一个操作的处理结果
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum RecoveryOutcome {
+    /// 删除了暂存内容，源文件保持不变
+    RolledBack,
+    /// 完成了放到目标位置（和删除源文件）的步骤
+    Completed,
+    Failed { error: String },
+}
+
+/// 启动恢复报告中的一项
+#[derive(Debug, Clone, Serialize)]
+pub struct RecoveryAction {
+    pub id: String,
+    pub kind: TransferKind,
+    pub source: String,
+    pub dest: String,
+    pub started_at: DateTime<Utc>,
+    pub outcome: RecoveryOutcome,
+}
+
+/// 复制和跨分区移动的意图日志
+///
+/// 操作开始前记录源路径、目标路径和暂存路径，结束后删除记录。
+/// 程序中途退出时记录会保留下来，下次启动时据此回滚或完成操作。
+/// 未指定日志文件时不记录（用于预览和测试）。
+#[derive(Debug, Default)]
+pub struct Journal {
+    path: Option<PathBuf>,
+    lock: Mutex<()>,
+}
+
+impl Journal {
+    pub fn open<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: Some(path.as_ref().to_path_buf()),
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// 记录一个即将开始的操作，返回记录 ID
+    pub fn begin(&self, kind: TransferKind, source: &Path, dest: &Path, staging: &Path) -> Result<String> {
+        let intent = Intent {
+            id: format!("{}-{}", Local::now().format("%Y%m%d%H%M%S%3f"), NEXT_ID.fetch_add(1, Ordering::Relaxed)),
+            kind,
+            source: source.to_string_lossy().to_string(),
+            dest: dest.to_string_lossy().to_string(),
+            staging: staging.to_string_lossy().to_string(),
+            phase: Phase::Staging,
+            started_at: Utc::now(),
+        };
+        let id = intent.id.clone();
+        self.update(|intents| intents.push(intent))?;
+        Ok(id)
+    }
+
+    /// 更新操作的阶段
+    pub fn set_phase(&self, id: &str, phase: Phase) -> Result<()> {
+        self.update(|intents| {
+            if let Some(intent) = intents.iter_mut().find(|i| i.id == id) {
+                intent.phase = phase;
+            }
+        })
+    }
+
+    /// 操作已结束（成功或已清理），删除记录
+    pub fn finish(&self, id: &str) {
+        if let Err(e) = self.update(|intents| intents.retain(|i| i.id != id)) {
+            warn!("删除操作记录失败: {}", e);
+        }
+    }
+
+    /// 处理上次运行中断的操作：暂存阶段的回滚，放置阶段的完成
+    pub fn recover(&self) -> Vec<RecoveryAction> {
+        let _guard = self.lock.lock();
+        let intents = match self.load() {
+            Ok(intents) => intents,
+            Err(e) => {
+                warn!("读取操作记录失败，跳过恢复: {}", e);
+                return Vec::new();
+            }
+        };
+
+        let mut report = Vec::new();
+        for intent in intents {
+            let result = match intent.phase {
+                Phase::Staging => transfer::remove_path(Path::new(&intent.staging))
+                    .with_context(|| format!("无法删除暂存内容: {}", intent.staging))
+                    .map(|_| RecoveryOutcome::RolledBack),
+                Phase::Committing => complete(&intent).map(|_| RecoveryOutcome::Completed),
+            };
+            let outcome = result.unwrap_or_else(|e| RecoveryOutcome::Failed { error: format!("{:#}", e) });
+
+            match &outcome {
+                RecoveryOutcome::Failed { error } => warn!("恢复中断的操作失败: {} -> {}: {}", intent.source, intent.dest, error),
+                outcome => info!("已恢复中断的操作 ({:?}): {} -> {}", outcome, intent.source, intent.dest),
+            }
+            report.push(RecoveryAction {
+                id: intent.id,
+                kind: intent.kind,
+                source: intent.source,
+                dest: intent.dest,
+                started_at: intent.started_at,
+                outcome,
+            });
+        }
+
+        // 失败的操作也不再重试，避免每次启动重复处理
+        if let Err(e) = self.save(&[]) {
+            warn!("清空操作记录失败: {}", e);
+        }
+        report
+    }
+
+    fn update<F: FnOnce(&mut Vec<Intent>)>(&self, change: F) -> Result<()> {
+        if self.path.is_none() {
+            return Ok(());
+        }
+        let _guard = self.lock.lock();
+        let mut intents = self.load()?;
+        change(&mut intents);
+        self.save(&intents)
+    }
+
+    fn load(&self) -> Result<Vec<Intent>> {
+        let path = match &self.path {
+            Some(p) if p.exists() => p,
+            _ => return Ok(Vec::new()),
+        };
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("无法读取操作记录: {:?}", path))?;
+        serde_json::from_str(&content).with_context(|| format!("无法解析操作记录: {:?}", path))
+    }
+
+    /// 先写临时文件再替换，日志本身不会因中途退出而损坏
+    fn save(&self, intents: &[Intent]) -> Result<()> {
+        let path = match &self.path {
+            Some(p) => p,
+            None => return Ok(()),
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let temp = path.with_extension("json.tmp");
+        let file = fs::File::create(&temp)?;
+        serde_json::to_writer_pretty(&file, intents)?;
+        file.sync_all()?;
+        fs::rename(&temp, path).with_context(|| format!("无法写入操作记录: {:?}", path))
+    }
+}
+
+/// 完成放置阶段中断的操作
+fn complete(intent: &Intent) -> Result<()> {
+    let staging = Path::new(&intent.staging);
+    let dest = Path::new(&intent.dest);
+    if staging.symlink_metadata().is_ok() {
+        transfer::commit_staged(staging, dest)?;
+    }
+
+    // 目标不存在时保留源文件，避免丢失数据
+    if intent.kind == TransferKind::Move {
+        let source = Path::new(&intent.source);
+        if dest.symlink_metadata().is_err() {
+            anyhow::bail!("目标位置不存在，已保留源文件: {}", intent.dest);
+        }
+        transfer::remove_path(source).with_context(|| format!("无法删除源文件: {}", intent.source))?;
+    }
+    Ok(())
+}
+
+/// 打开意图日志并恢复上次中断的操作，返回恢复报告
+pub fn init<P: AsRef<Path>>(path: P) -> Vec<RecoveryAction> {
+    let journal = Journal::open(path);
+    let report = journal.recover();
+    if JOURNAL.set(journal).is_err() {
+        warn!("操作记录已初始化");
+    }
+    report
+}
+
+/// 全局意图日志（未初始化时为不记录的空日志）
+pub fn global() -> &'static Journal {
+    JOURNAL.get_or_init(Journal::default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recover_interrupted_operations() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        fs::create_dir_all(dir.join("src/docs")).unwrap();
+        fs::create_dir_all(dir.join("dst/.docs.floatsort-part")).unwrap();
+        fs::write(dir.join("src/a.txt"), "abc").unwrap();
+        fs::write(dir.join("src/docs/b.txt"), "hello").unwrap();
+        fs::write(dir.join("dst/.a.txt.floatsort-part"), "abc").unwrap();
+        fs::write(dir.join("dst/.docs.floatsort-part/b.txt"), "hel").unwrap();
+
+        // 文件已校验、正在放置；文件夹还在暂存阶段
+        let journal = Journal::open(dir.join("journal.json"));
+        let file = journal
+            .begin(TransferKind::Move, &dir.join("src/a.txt"), &dir.join("dst/a.txt"), &dir.join("dst/.a.txt.floatsort-part"))
+            .unwrap();
+        journal.set_phase(&file, Phase::Committing).unwrap();
+        journal
+            .begin(TransferKind::Move, &dir.join("src/docs"), &dir.join("dst/docs"), &dir.join("dst/.docs.floatsort-part"))
+            .unwrap();
+
+        let report = Journal::open(dir.join("journal.json")).recover();
+        assert_eq!(report.len(), 2);
+        assert!(matches!(report[0].outcome, RecoveryOutcome::Completed));
+        assert!(matches!(report[1].outcome, RecoveryOutcome::RolledBack));
+        assert_eq!(fs::read_to_string(dir.join("dst/a.txt")).unwrap(), "abc");
+        assert!(!dir.join("src/a.txt").exists());
+        assert!(!dir.join("dst/.docs.floatsort-part").exists() && dir.join("src/docs/b.txt").exists());
+        assert!(journal.recover().is_empty());
+    }
+}
@@ -1,19 +1,44 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod archive;
 mod config;
+mod conflict;
+mod counters;
 mod file_monitor;
 mod rule_engine;
+mod exec;
 mod file_ops;
+mod history;
+mod image_convert;
+mod journal;
 mod models;
+mod path_vars;
+mod quarantine;
+mod rule_packs;
+mod rules_file;
+mod sanitize;
 mod scheduler;
+mod thumbnail;
+mod trash_log;
+mod transfer;
 mod window_snap;
 mod i18n;
 
 use config::{AppConfig, WatchFolder};
+use conflict::{ConflictQueue, ConflictResolution, CopyNameFormat, PendingConflict};
+use counters::CounterStore;
+use file_ops::OrganizeContext;
+use history::RunHistory;
+use journal::RecoveryAction;
+use quarantine::{Quarantine, QuarantineEntry};
+use thumbnail::{Thumbnail, ThumbnailCache};
+use trash_log::{TrashLog, TrashedItem};
+use transfer::{Operations, PreserveMetadata, ProgressEvent};
 use file_monitor::FileMonitor;
+use rules_file::RulesFileWatcher;
 use scheduler::Scheduler;
-use models::Rule;
+use models::{ConflictStrategy, Rule, RuleSet};
 use std::sync::{Arc, Mutex};
 use std::collections::HashSet;
 use std::fs;
@@ -29,6 +54,15 @@ struct AppState {
     stats: Arc<Mutex<Statistics>>,
     processed_files: Arc<Mutex<HashSet<String>>>, // 记录已处理的文件路径
     window_snap_running: Arc<Mutex<bool>>, // 窗口折叠功能是否运行中
+    rules_watcher: Arc<Mutex<Option<RulesFileWatcher>>>, // 外部规则文件监控器
+    counter_store: Arc<Mutex<CounterStore>>, // 序号计数器状态
+    history: Arc<RunHistory>, // 运行历史
+    thumbnails: Arc<ThumbnailCache>, // 缩略图缓存
+    quarantine: Arc<Quarantine>, // 删除文件的隔离区
+    trash_log: Arc<TrashLog>, // 移到回收站的文件记录
+    operations: Arc<Operations>, // 正在进行的复制操作
+    recovery: Vec<RecoveryAction>, // 启动时恢复的中断操作
+    conflicts: Arc<ConflictQueue>, // 等待用户处理的冲突
 }
 
 // 统计信息
@@ -50,10 +84,12 @@ fn get_config(state: State<AppState>) -> Result<AppConfig, String> {
 
 // Tauri 命令：保存配置
 #[tauri::command]
-fn save_config(config: AppConfig, state: State<AppState>) -> Result<(), String> {
+fn save_config(mut config: AppConfig, state: State<AppState>) -> Result<(), String> {
     let mut app_config = state.config.lock().map_err(|e| e.to_string())?;
+    config.rules.iter_mut().for_each(|r| { r.migrate_action(); });
     *app_config = config.clone();
     config.save_to_file("data/config.json").map_err(|e| e.to_string())?;
+    conflict::set_copy_name_format(config.copy_name_format.clone());
     info!("配置已保存");
     Ok(())
 }
@@ -134,8 +170,9 @@ fn save_language_setting(language: String, state: State<AppState>) -> Result<(),
 
 // Tauri 命令：添加规则
 #[tauri::command]
-fn add_rule(rule: Rule, state: State<AppState>) -> Result<(), String> {
+fn add_rule(mut rule: Rule, state: State<AppState>) -> Result<(), String> {
     let mut config = state.config.lock().map_err(|e| e.to_string())?;
+    rule.migrate_action();
     config.rules.push(rule);
     config.save_to_file("data/config.json").map_err(|e| e.to_string())?;
     info!("规则已添加");
@@ -157,6 +194,9 @@ fn remove_rule(rule_id: String, state: State<AppState>) -> Result<(), String> {
     if let Some(index) = config.rules.iter().position(|r| r.id == rule_id) {
         let rule_name = config.rules[index].name.clone();
         config.rules.remove(index);
+        for rule_set in config.rule_sets.iter_mut() {
+            rule_set.rule_ids.retain(|id| id != &rule_id);
+        }
         config.save_to_file("data/config.json").map_err(|e| e.to_string())?;
         info!("规则已删除: {}", rule_name);
         Ok(())
@@ -167,8 +207,9 @@ fn remove_rule(rule_id: String, state: State<AppState>) -> Result<(), String> {
 
 // Tauri 命令：更新规则
 #[tauri::command]
-fn update_rule(rule_id: String, rule: Rule, state: State<AppState>) -> Result<(), String> {
+fn update_rule(rule_id: String, mut rule: Rule, state: State<AppState>) -> Result<(), String> {
     let mut config = state.config.lock().map_err(|e| e.to_string())?;
+    rule.migrate_action();
     
     if let Some(index) = config.rules.iter().position(|r| r.id == rule_id) {
         config.rules[index] = rule;
@@ -204,6 +245,148 @@ fn reorder_rules(rule_ids: Vec<String>, state: State<AppState>) -> Result<(), St
     Ok(())
 }
 
+// ============ 规则集命令 ============
+
+// Tauri 命令：获取所有规则集
+#[tauri::command]
+fn get_rule_sets(state: State<AppState>) -> Result<Vec<RuleSet>, String> {
+    let config = state.config.lock().map_err(|e| e.to_string())?;
+    Ok(config.rule_sets.clone())
+}
+
+// Tauri 命令：添加规则集
+#[tauri::command]
+fn add_rule_set(rule_set: RuleSet, state: State<AppState>) -> Result<(), String> {
+    let mut config = state.config.lock().map_err(|e| e.to_string())?;
+    
+    if config.rule_sets.iter().any(|s| s.id == rule_set.id) {
+        return Err("规则集已存在".to_string());
+    }
+    
+    let set_name = rule_set.name.clone();
+    config.rule_sets.push(rule_set);
+    config.save_to_file("data/config.json").map_err(|e| e.to_string())?;
+    info!("规则集已添加: {}", set_name);
+    Ok(())
+}
+
+// Tauri 命令：更新规则集
+#[tauri::command]
+fn update_rule_set(set_id: String, rule_set: RuleSet, state: State<AppState>) -> Result<(), String> {
+    let mut config = state.config.lock().map_err(|e| e.to_string())?;
+    
+    if let Some(index) = config.rule_sets.iter().position(|s| s.id == set_id) {
+        config.rule_sets[index] = rule_set;
+        config.save_to_file("data/config.json").map_err(|e| e.to_string())?;
+        info!("规则集已更新");
+        Ok(())
+    } else {
+        Err("规则集不存在".to_string())
+    }
+}
+
+// Tauri 命令：删除规则集（规则本身保留）
+#[tauri::command]
+fn remove_rule_set(set_id: String, state: State<AppState>) -> Result<(), String> {
+    let mut config = state.config.lock().map_err(|e| e.to_string())?;
+    
+    if let Some(index) = config.rule_sets.iter().position(|s| s.id == set_id) {
+        let set_name = config.rule_sets[index].name.clone();
+        config.rule_sets.remove(index);
+        
+        // 同时解除文件夹与该规则集的关联
+        for folder in config.folders.iter_mut() {
+            folder.rule_set_ids.retain(|id| id != &set_id);
+        }
+        
+        config.save_to_file("data/config.json").map_err(|e| e.to_string())?;
+        info!("规则集已删除: {}", set_name);
+        Ok(())
+    } else {
+        Err("规则集不存在".to_string())
+    }
+}
+
+// Tauri 命令：整体启用/禁用规则集
+#[tauri::command]
+fn set_rule_set_enabled(set_id: String, enabled: bool, state: State<AppState>) -> Result<(), String> {
+    let mut config = state.config.lock().map_err(|e| e.to_string())?;
+    
+    let set_name = {
+        if let Some(rule_set) = config.rule_sets.iter_mut().find(|s| s.id == set_id) {
+            rule_set.enabled = enabled;
+            rule_set.name.clone()
+        } else {
+            return Err("规则集不存在".to_string());
+        }
+    };
+    
+    config.save_to_file("data/config.json").map_err(|e| e.to_string())?;
+    info!("规则集 {} 启用状态: {}", set_name, enabled);
+    Ok(())
+}
+
+// Tauri 命令：重新排序规则集（按传入顺序重新分配优先级）
+#[tauri::command]
+fn reorder_rule_sets(set_ids: Vec<String>, state: State<AppState>) -> Result<(), String> {
+    let mut config = state.config.lock().map_err(|e| e.to_string())?;
+    
+    // 验证所有规则集都被包含了
+    if set_ids.len() != config.rule_sets.len()
+        || !config.rule_sets.iter().all(|s| set_ids.contains(&s.id))
+    {
+        return Err("规则集ID列表不完整".to_string());
+    }
+    
+    for rule_set in config.rule_sets.iter_mut() {
+        if let Some(position) = set_ids.iter().position(|id| id == &rule_set.id) {
+            rule_set.priority = position as i32;
+        }
+    }
+    config.rule_sets.sort_by_key(|s| s.priority);
+    
+    config.save_to_file("data/config.json").map_err(|e| e.to_string())?;
+    info!("规则集顺序已更新");
+    Ok(())
+}
+
+// ============ 规则包命令 ============
+
+// Tauri 命令：列出内置规则包
+#[tauri::command]
+fn list_rule_packs(state: State<AppState>) -> Result<Vec<rule_packs::PackSummary>, String> {
+    let config = state.config.lock().map_err(|e| e.to_string())?;
+    Ok(rule_packs::list_packs(&config))
+}
+
+// Tauri 命令：预览规则包对文件夹的整理效果
+#[tauri::command]
+fn preview_rule_pack(pack_id: String, folder_id: String, state: State<AppState>) -> Result<Vec<rule_packs::PackPreviewItem>, String> {
+    let folder_path = {
+        let config = state.config.lock().map_err(|e| e.to_string())?;
+        config.folders.iter()
+            .find(|f| f.id == folder_id)
+            .map(|f| config.folder_path(f))
+            .ok_or_else(|| "文件夹不存在".to_string())?
+    };
+    let pack = rule_packs::find_pack(&pack_id).ok_or_else(|| "规则包不存在".to_string())?;
+    
+    rule_packs::preview_pack(pack, &folder_path).map_err(|e| e.to_string())
+}
+
+// Tauri 命令：安装（或更新）规则包到文件夹
+#[tauri::command]
+fn install_rule_pack(pack_id: String, folder_id: String, state: State<AppState>) -> Result<rule_packs::InstallReport, String> {
+    let mut config = state.config.lock().map_err(|e| e.to_string())?;
+    let pack = rule_packs::find_pack(&pack_id).ok_or_else(|| "规则包不存在".to_string())?;
+    
+    let report = rule_packs::install_pack(&mut config, pack, &folder_id).map_err(|e| e.to_string())?;
+    config.save_to_file("data/config.json").map_err(|e| e.to_string())?;
+    info!("规则包 {} 已安装到文件夹 {}: 新增 {}, 更新 {}, 保留 {}, 移除 {}",
+          pack_id, folder_id, report.added.len(), report.updated.len(), report.kept.len(), report.removed.len());
+    Ok(report)
+}
+
 // Tauri 命令：打开日志文件夹
 #[tauri::command]
 fn open_log_folder() -> Result<(), String> {
@@ -320,6 +503,120 @@ fn save_log_retention_setting(retention_days: i32, state: State<AppState>) -> Re
     Ok(())
 }
 
+// Tauri 命令：保存隔离区设置（是否启用、保留天数）
+#[tauri::command]
+fn save_quarantine_settings(enabled: bool, retention_days: i32, state: State<AppState>) -> Result<(), String> {
+    let mut config = state.config.lock().map_err(|e| e.to_string())?;
+    config.use_quarantine = enabled;
+    config.quarantine_retention_days = retention_days;
+    config.save_to_file("data/config.json").map_err(|e| e.to_string())?;
+    info!("隔离区设置已保存: 启用={}, 保留{}天", enabled, retention_days);
+    Ok(())
+}
+
+// Tauri 命令：保存复制时保留元数据的设置
+#[tauri::command]
+fn save_preserve_settings(preserve: PreserveMetadata, state: State<AppState>) -> Result<(), String> {
+    let mut config = state.config.lock().map_err(|e| e.to_string())?;
+    config.preserve_metadata = preserve;
+    config.save_to_file("data/config.json").map_err(|e| e.to_string())?;
+    info!("元数据保留设置已保存: {:?}", preserve);
+    Ok(())
+}
+
+// Tauri 命令：保存全局副本名称格式（为空时使用当前语言的默认格式），返回示例文件名
+#[tauri::command]
+fn save_copy_name_format(format: Option<String>, state: State<AppState>) -> Result<String, String> {
+    let format = format.filter(|f| !f.trim().is_empty());
+    if let Some(pattern) = &format {
+        CopyNameFormat::parse(pattern).map_err(|e| e.to_string())?;
+    }
+    let mut config = state.config.lock().map_err(|e| e.to_string())?;
+    config.copy_name_format = format.clone();
+    config.save_to_file("data/config.json").map_err(|e| e.to_string())?;
+    conflict::set_copy_name_format(format);
+    let example = CopyNameFormat::effective(None).name("report", "pdf", 2);
+    info!("副本名称格式已保存，示例: {}", example);
+    Ok(example)
+}
+
+// Tauri 命令：列出隔离区中的文件（最新的在前）
+#[tauri::command]
+fn list_quarantine(state: State<AppState>) -> Result<Vec<QuarantineEntry>, String> {
+    state.quarantine.list().map_err(|e| e.to_string())
+}
+
+// Tauri 命令：将隔离区中的文件恢复到原位置，返回恢复后的路径
+#[tauri::command]
+fn restore_quarantined(id: String, state: State<AppState>) -> Result<String, String> {
+    state.quarantine.restore(&id).map_err(|e| e.to_string())
+}
+
+// Tauri 命令：彻底删除隔离区中的文件（不指定 ID 时清空隔离区），返回删除的数量
+#[tauri::command]
+fn purge_quarantine(id: Option<String>, state: State<AppState>) -> Result<usize, String> {
+    match id {
+        Some(id) => state.quarantine.purge(&id).map(|_| 1).map_err(|e| e.to_string()),
+        None => state.quarantine.purge_before(None).map_err(|e| e.to_string()),
+    }
+}
+
+// Tauri 命令：列出 FloatSort 移到回收站且仍未清空的文件（最新的在前）
+#[tauri::command]
+fn list_trashed(state: State<AppState>) -> Result<Vec<TrashedItem>, String> {
+    state.trash_log.list().map_err(|e| e.to_string())
+}
+
+// Tauri 命令：将回收站中的文件恢复到原位置，返回恢复后的路径
+#[tauri::command]
+fn restore_trashed(id: String, state: State<AppState>) -> Result<String, String> {
+    state.trash_log.restore(&id).map_err(|e| e.to_string())
+}
+
+// Tauri 命令：从回收站彻底删除早于指定天数的文件（不指定时全部删除），返回删除的数量
+#[tauri::command]
+fn purge_trashed(older_than: Option<u32>, state: State<AppState>) -> Result<usize, String> {
+    state.trash_log.purge(older_than).map_err(|e| e.to_string())
+}
+
+// Tauri 命令：取消正在进行的复制或跨分区移动，返回操作是否存在
+#[tauri::command]
+fn cancel_operation(id: String, state: State<AppState>) -> Result<bool, String> {
+    Ok(state.operations.cancel(&id))
+}
+
+// Tauri 命令：获取启动时恢复上次中断的复制和移动操作的报告
+#[tauri::command]
+fn get_recovery_report(state: State<AppState>) -> Result<Vec<RecoveryAction>, String> {
+    Ok(state.recovery.clone())
+}
+
+// Tauri 命令：列出等待处理的冲突（冲突策略为询问时产生）
+#[tauri::command]
+fn list_conflicts(state: State<AppState>) -> Result<Vec<PendingConflict>, String> {
+    Ok(state.conflicts.list())
+}
+
+// Tauri 命令：按所选策略处理冲突；remember 为 true 时同一批次的其他冲突和后续冲突都使用该策略
+#[tauri::command]
+async fn resolve_conflict(id: String, strategy: ConflictStrategy, remember: Option<bool>, state: State<'_, AppState>) -> Result<Vec<ConflictResolution>, String> {
+    let conflicts = state.conflicts
+        .take(&id, &strategy, remember.unwrap_or(false))
+        .map_err(|e| e.to_string())?;
+    let config = state.config.lock().map_err(|e| e.to_string())?.clone();
+    let ctx = organize_context(&config, &state);
+    Ok(conflicts
+        .iter()
+        .map(|conflict| file_ops::resolve_conflict(conflict, &strategy, &ctx))
+        .collect())
+}
+
+// Tauri 命令：放弃处理冲突（文件保留在原位置），返回冲突是否存在
+#[tauri::command]
+fn dismiss_conflict(id: String, state: State<AppState>) -> Result<bool, String> {
+    Ok(state.conflicts.dismiss(&id))
+}
+
 // Tauri 命令：选择文件夹
 #[tauri::command]
 async fn select_folder(window: tauri::Window) -> Result<Option<String>, String> {
@@ -429,9 +726,17 @@ fn open_file_location(path: String) -> Result<(), String> {
 
 // Tauri 命令：获取所有文件夹
 #[tauri::command]
-fn get_folders(state: State<AppState>) -> Result<Vec<WatchFolder>, String> {
+fn get_folders(state: State<AppState>) -> Result<Vec<serde_json::Value>, String> {
     let config = state.config.lock().map_err(|e| e.to_string())?;
-    Ok(config.folders.clone())
+    
+    // 附带展开变量后的实际路径，供前端匹配文件所属的文件夹
+    config.folders.iter()
+        .map(|folder| {
+            let mut value = serde_json::to_value(folder).map_err(|e| e.to_string())?;
+            value["resolved_path"] = serde_json::json!(config.folder_path(folder).to_string_lossy());
+            Ok(value)
+        })
+        .collect()
 }
 
 // Tauri 命令：添加文件夹
@@ -519,6 +824,35 @@ fn update_folder_rules(folder_id: String, rule_ids: Vec<String>, state: State<Ap
     Ok(())
 }
 
+// Tauri 命令：更新文件夹关联的规则集
+#[tauri::command]
+fn update_folder_rule_sets(folder_id: String, rule_set_ids: Vec<String>, state: State<AppState>) -> Result<(), String> {
+    let mut config = state.config.lock().map_err(|e| e.to_string())?;
+    
+    let folder_name = {
+        if let Some(folder) = config.folders.iter_mut().find(|f| f.id == folder_id) {
+            folder.rule_set_ids = rule_set_ids;
+            folder.name.clone()
+        } else {
+            return Err("文件夹不存在".to_string());
+        }
+    };
+    
+    config.save_to_file("data/config.json").map_err(|e| e.to_string())?;
+    info!("文件夹 {} 的规则集已更新", folder_name);
+    Ok(())
+}
+
+// Tauri 命令：获取文件夹生效的规则（已按评估顺序排列）
+#[tauri::command]
+fn get_folder_rules(folder_id: String, state: State<AppState>) -> Result<Vec<Rule>, String> {
+    let config = state.config.lock().map_err(|e| e.to_string())?;
+    let folder = config.folders.iter()
+        .find(|f| f.id == folder_id)
+        .ok_or_else(|| "文件夹不存在".to_string())?;
+    Ok(config.rules_for_folder(folder))
+}
+
 // ============ 监控命令（更新版） ============
 
 // Tauri 命令：启动所有已启用文件夹的监控
@@ -577,9 +911,36 @@ fn stop_monitoring(state: State<AppState>) -> Result<(), String> {
     Ok(())
 }
 
+// 获取生效的规则：指定文件夹时使用文件夹关联的规则和规则集，否则使用全局规则（不含关联到文件夹的规则集）
+fn effective_rules(config: &AppConfig, folder_id: Option<&str>) -> Result<Vec<Rule>, String> {
+    match folder_id {
+        Some(id) => {
+            let folder = config.folders.iter()
+                .find(|f| f.id == id)
+                .ok_or_else(|| "文件夹不存在".to_string())?;
+            Ok(config.rules_for_folder(folder))
+        }
+        None => Ok(config.active_rules()),
+    }
+}
+
+// 创建整理上下文（共享计数器状态和运行历史）
+fn organize_context(config: &AppConfig, state: &AppState) -> OrganizeContext {
+    let ctx = OrganizeContext::from_config(config, state.counter_store.clone())
+        .with_history(state.history.clone())
+        .with_trash_log(state.trash_log.clone())
+        .with_operations(state.operations.clone())
+        .with_conflicts(state.conflicts.clone());
+    if config.use_quarantine {
+        ctx.with_quarantine(state.quarantine.clone())
+    } else {
+        ctx
+    }
+}
+
 // Tauri 命令：手动整理文件
 #[tauri::command]
-async fn process_file(path: String, window: tauri::Window, state: State<'_, AppState>) -> Result<String, String> {
+async fn process_file(path: String, folder_id: Option<String>, batch_id: Option<String>, window: tauri::Window, state: State<'_, AppState>) -> Result<String, String> {
     info!("[自动处理] 开始处理文件: {}", path);
     
     // 检查文件是否已处理过
@@ -593,12 +954,15 @@ async fn process_file(path: String, window: tauri::Window, state: State<'_, AppS
     
     let config = state.config.lock().map_err(|e| e.to_string())?.clone();
     let original_path = path.clone();
+    let rules = effective_rules(&config, folder_id.as_deref())?;
     
-    let result = file_ops::organize_single_file(&path, &config.rules)
+    let ctx = organize_context(&config, &state).with_batch(batch_id);
+    
+    let result = file_ops::organize_single_file(&path, &rules, &ctx)
         .map_err(|e| e.to_string())?;
     
-    // 判断文件是否被成功移动（返回值不是错误提示信息）
-    let is_organized = !result.is_empty() && result != "文件未匹配任何规则";
+    // 判断文件是否被成功移动（返回值不是错误提示信息，也不是等待处理冲突）
+    let is_organized = !result.is_empty() && result != "文件未匹配任何规则" && result != file_ops::CONFLICT_PENDING;
     
     // 只有文件被成功移动才记录为已处理
     if is_organized {
@@ -634,7 +998,7 @@ async fn process_file(path: String, window: tauri::Window, state: State<'_, AppS
 
 // Tauri 命令：使用指定规则整理文件
 #[tauri::command]
-async fn process_file_with_rule(path: String, rule_id: String, window: tauri::Window, state: State<'_, AppState>) -> Result<String, String> {
+async fn process_file_with_rule(path: String, rule_id: String, batch_id: Option<String>, window: tauri::Window, state: State<'_, AppState>) -> Result<String, String> {
     let config = state.config.lock().map_err(|e| e.to_string())?.clone();
     let original_path = path.clone();
     
@@ -644,10 +1008,11 @@ async fn process_file_with_rule(path: String, rule_id: String, window: tauri::Wi
         .ok_or_else(|| "规则不存在".to_string())?;
     
     // 使用单个规则进行整理
-    let result = file_ops::organize_single_file(&path, &vec![rule.clone()])
+    let ctx = organize_context(&config, &state).with_batch(batch_id);
+    let result = file_ops::organize_single_file(&path, &vec![rule.clone()], &ctx)
         .map_err(|e| e.to_string())?;
     
-    let is_organized = !result.is_empty() && result != "文件未匹配任何规则";
+    let is_organized = !result.is_empty() && result != "文件未匹配任何规则" && result != file_ops::CONFLICT_PENDING;
     
     // 发送整理成功事件到前端
     if is_organized {
@@ -678,7 +1043,7 @@ async fn process_file_with_rule(path: String, rule_id: String, window: tauri::Wi
 
 // Tauri 命令：预览文件整理（不实际移动文件）
 #[tauri::command]
-async fn preview_file_organization(path: String, state: State<'_, AppState>) -> Result<serde_json::Value, String> {
+async fn preview_file_organization(path: String, folder_id: Option<String>, state: State<'_, AppState>) -> Result<serde_json::Value, String> {
     use std::path::Path;
     
     let config = state.config.lock().map_err(|e| e.to_string())?.clone();
@@ -687,39 +1052,34 @@ async fn preview_file_organization(path: String, state: State<'_, AppState>) ->
     let file_info = file_ops::get_file_info(Path::new(&path))
         .map_err(|e| e.to_string())?;
     
-    // 查找匹配的规则
-    let engine = crate::rule_engine::RuleEngine::new(config.rules.clone());
-    
-    if let Some(rule_match) = engine.find_matching_rule(&file_info) {
-        // 计算目标路径（使用当前目录作为基础路径）
-        let base_path = Path::new(&path).parent()
-            .ok_or_else(|| "无法获取父目录".to_string())?;
+    // 查找所有应执行的规则
+    let engine = crate::rule_engine::RuleEngine::new(effective_rules(&config, folder_id.as_deref())?);
+    let matches = engine.find_matching_rules(&file_info);
+    let ctx = organize_context(&config, &state);
+    
+    if !matches.is_empty() {
+        // 多条规则依次执行时，显示所有规则名称
+        let rule_name = matches.iter()
+            .map(|m| m.rule.name.as_str())
+            .collect::<Vec<_>>()
+            .join(" → ");
         
-        if let Some(dest_path) = engine.get_destination_path(&rule_match.rule.action, &file_info, base_path, &rule_match.regex_captures) {
+        // 依次推演动作流水线，得到最终的目标路径
+        if let Some(full_target_path) = file_ops::preview_matches(&matches, &file_info, &engine, &ctx) {
             // 如果目标路径是回收站，直接返回
-            if dest_path == "已移动到回收站" || dest_path == "{recycle}" {
+            if full_target_path == "{recycle}" {
                 return Ok(serde_json::json!({
                     "matched": true,
-                    "rule_name": rule_match.rule.name,
+                    "rule_name": rule_name,
                     "original_path": path,
                     "target_path": "回收站",
                     "is_directory": file_info.is_directory,
                 }));
             }
             
-            // 构建完整的目标文件路径（目标文件夹 + 文件名）
-            let file_name = Path::new(&path)
-                .file_name()
-                .and_then(|n| n.to_str())
-                .unwrap_or("");
-            let full_target_path = Path::new(&dest_path)
-                .join(file_name)
-                .to_string_lossy()
-                .to_string();
-            
             return Ok(serde_json::json!({
                 "matched": true,
-                "rule_name": rule_match.rule.name,
+                "rule_name": rule_name,
                 "original_path": path,
                 "target_path": full_target_path,
                 "is_directory": file_info.is_directory,
@@ -755,36 +1115,26 @@ async fn preview_file_organization_with_rule(path: String, rule_id: String, stat
     // 检查文件是否匹配该规则
     let engine = crate::rule_engine::RuleEngine::new(vec![rule.clone()]);
     
-    if let Some(rule_match) = engine.find_matching_rule(&file_info) {
-        // 计算目标路径
-        let base_path = Path::new(&path).parent()
-            .ok_or_else(|| "无法获取父目录".to_string())?;
-        
-        if let Some(dest_path) = engine.get_destination_path(&rule_match.rule.action, &file_info, base_path, &rule_match.regex_captures) {
+    let matches = engine.find_matching_rules(&file_info);
+    let ctx = organize_context(&config, &state);
+    
+    if !matches.is_empty() {
+        // 依次推演动作流水线，得到最终的目标路径
+        if let Some(full_target_path) = file_ops::preview_matches(&matches, &file_info, &engine, &ctx) {
             // 如果目标路径是回收站，直接返回
-            if dest_path == "已移动到回收站" || dest_path == "{recycle}" {
+            if full_target_path == "{recycle}" {
                 return Ok(serde_json::json!({
                     "matched": true,
-                    "rule_name": rule_match.rule.name,
+                    "rule_name": rule.name,
                     "original_path": path,
                     "target_path": "回收站",
                     "is_directory": file_info.is_directory,
                 }));
             }
             
-            // 构建完整的目标文件路径（目标文件夹 + 文件名）
-            let file_name = Path::new(&path)
-                .file_name()
-                .and_then(|n| n.to_str())
-                .unwrap_or("");
-            let full_target_path = Path::new(&dest_path)
-                .join(file_name)
-                .to_string_lossy()
-                .to_string();
-            
             return Ok(serde_json::json!({
                 "matched": true,
-                "rule_name": rule_match.rule.name,
+                "rule_name": rule.name,
                 "original_path": path,
                 "target_path": full_target_path,
                 "is_directory": file_info.is_directory,
@@ -885,14 +1235,136 @@ fn export_config(state: State<AppState>) -> Result<serde_json::Value, String> {
 
 // Tauri 命令：导入配置
 #[tauri::command]
-fn import_config(config: AppConfig, state: State<AppState>) -> Result<(), String> {
+fn import_config(mut config: AppConfig, state: State<AppState>) -> Result<(), String> {
     let mut app_config = state.config.lock().map_err(|e| e.to_string())?;
+    // 兼容旧版导出文件中的单一 action 字段
+    config.rules.iter_mut().for_each(|r| { r.migrate_action(); });
     *app_config = config.clone();
     config.save_to_file("data/config.json").map_err(|e| e.to_string())?;
     info!("配置已导入并保存");
     Ok(())
 }
 
+// Tauri 命令：导出规则、规则集和文件夹到 TOML/YAML 规则文件
+#[tauri::command]
+fn export_rules_file(path: String, state: State<AppState>) -> Result<(), String> {
+    let config = state.config.lock().map_err(|e| e.to_string())?;
+    rules_file::RulesFile::from_config(&config)
+        .save(&path)
+        .map_err(|e| format!("{:#}", e))?;
+    Ok(())
+}
+
+// Tauri 命令：从 TOML/YAML 规则文件导入（校验通过后才会替换）
+#[tauri::command]
+fn import_rules_file(path: String, state: State<AppState>) -> Result<(), String> {
+    let mut config = state.config.lock().map_err(|e| e.to_string())?;
+    let new_config = rules_file::load_and_apply(std::path::Path::new(&path), &config)
+        .map_err(|e| format!("{:#}", e))?;
+    *config = new_config;
+    config.save_to_file("data/config.json").map_err(|e| e.to_string())?;
+    info!("规则文件已导入: {}", path);
+    Ok(())
+}
+
+// Tauri 命令：设置（或取消）需要监控并热加载的规则文件
+#[tauri::command]
+fn set_rules_file(path: Option<String>, window: tauri::Window, state: State<AppState>) -> Result<(), String> {
+    // 先停止旧的监控
+    *state.rules_watcher.lock().map_err(|e| e.to_string())? = None;
+    
+    {
+        let mut config = state.config.lock().map_err(|e| e.to_string())?;
+        if let Some(path) = &path {
+            // 立即加载一次，确保文件有效
+            let mut new_config = rules_file::load_and_apply(std::path::Path::new(path), &config)
+                .map_err(|e| format!("{:#}", e))?;
+            new_config.rules_file = Some(path.clone());
+            *config = new_config;
+        } else {
+            config.rules_file = None;
+        }
+        config.save_to_file("data/config.json").map_err(|e| e.to_string())?;
+    }
+    
+    if let Some(path) = path {
+        let watcher = RulesFileWatcher::new(path.clone().into(), state.config.clone(), window)?;
+        *state.rules_watcher.lock().map_err(|e| e.to_string())? = Some(watcher);
+        info!("已启用规则文件热加载: {}", path);
+    } else {
+        info!("已停用规则文件热加载");
+    }
+    Ok(())
+}
+
+// Tauri 命令：设置（或删除）路径变量
+#[tauri::command]
+fn set_path_variable(name: String, value: Option<String>, state: State<AppState>) -> Result<(), String> {
+    let mut config = state.config.lock().map_err(|e| e.to_string())?;
+    match value {
+        Some(value) => {
+            info!("路径变量已设置: {} = {}", name, value);
+            config.path_variables.insert(name, value);
+        }
+        None => {
+            info!("路径变量已删除: {}", name);
+            config.path_variables.remove(&name);
+        }
+    }
+    config.save_to_file("data/config.json").map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// Tauri 命令：展开路径中的变量（用于在界面中预览实际路径）
+#[tauri::command]
+fn resolve_path(path: String, state: State<AppState>) -> Result<String, String> {
+    let config = state.config.lock().map_err(|e| e.to_string())?;
+    config.expand_path(&path).map_err(|e| e.to_string())
+}
+
+// Tauri 命令：获取运行历史（最新的在前）
+#[tauri::command]
+fn get_run_history(limit: Option<usize>, state: State<AppState>) -> Result<Vec<history::HistoryEntry>, String> {
+    state.history.list(limit.unwrap_or(200)).map_err(|e| e.to_string())
+}
+
+// Tauri 命令：清空运行历史
+#[tauri::command]
+fn clear_run_history(state: State<AppState>) -> Result<(), String> {
+    state.history.clear().map_err(|e| e.to_string())
+}
+
+// Tauri 命令：获取文件缩略图（图片返回缩略图，其他文件返回通用图标）
+// inline 为 false 时返回缓存文件路径而不是 data URL
+#[tauri::command]
+async fn get_thumbnail(path: String, size: u32, inline: Option<bool>, state: State<'_, AppState>) -> Result<Thumbnail, String> {
+    let cache = state.thumbnails.clone();
+    tauri::async_runtime::spawn_blocking(move || cache.get(std::path::Path::new(&path), size, inline.unwrap_or(true)))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+// Tauri 命令：清空缩略图缓存
+#[tauri::command]
+fn clear_thumbnail_cache(state: State<AppState>) -> Result<(), String> {
+    state.thumbnails.clear().map_err(|e| e.to_string())
+}
+
+// Tauri 命令：获取序号计数器的当前状态（计数器名称 -> 分组 -> 最后使用的序号）
+#[tauri::command]
+fn get_counter_values(state: State<AppState>) -> Result<serde_json::Value, String> {
+    let store = state.counter_store.lock().map_err(|e| e.to_string())?;
+    serde_json::to_value(store.values()).map_err(|e| e.to_string())
+}
+
+// Tauri 命令：重置序号计数器
+#[tauri::command]
+fn reset_counter(name: String, state: State<AppState>) -> Result<(), String> {
+    let mut store = state.counter_store.lock().map_err(|e| e.to_string())?;
+    store.reset(&name).map_err(|e| e.to_string())
+}
+
 // Tauri 命令：保存文件
 #[tauri::command]
 fn save_file(path: String, content: String) -> Result<(), String> {
@@ -965,6 +1437,7 @@ fn main() {
 
     // 初始化i18n语言设置
     i18n::set_language(&config.language);
+    conflict::set_copy_name_format(config.copy_name_format.clone());
     
     // 自动清理过期日志
     let retention_days = config.log_retention_days;
@@ -981,6 +1454,19 @@ fn main() {
         }
     }
 
+    // 加载序号计数器状态
+    let counter_store = CounterStore::load_or_default("data/counters.json")
+        .unwrap_or_else(|e| {
+            info!("加载计数器状态失败，使用空状态: {}", e);
+            CounterStore::default()
+        });
+
+    // 回滚或完成上次中断的复制和移动操作
+    let recovery = journal::init("data/transfer_journal.json");
+    if !recovery.is_empty() {
+        info!("启动时恢复了 {} 个中断的操作", recovery.len());
+    }
+
     // 创建应用状态
     let app_state = AppState {
         config: Arc::new(Mutex::new(config)),
@@ -988,6 +1474,15 @@ fn main() {
         stats: Arc::new(Mutex::new(Statistics::default())),
         processed_files: Arc::new(Mutex::new(HashSet::new())),
         window_snap_running: Arc::new(Mutex::new(false)),
+        rules_watcher: Arc::new(Mutex::new(None)),
+        counter_store: Arc::new(Mutex::new(counter_store)),
+        history: Arc::new(RunHistory::open("data/history.jsonl")),
+        thumbnails: Arc::new(ThumbnailCache::new("data/thumbnails", thumbnail::DEFAULT_CACHE_BYTES)),
+        quarantine: Arc::new(Quarantine::open("data/quarantine")),
+        trash_log: Arc::new(TrashLog::open("data/trash_log.json")),
+        operations: Arc::new(Operations::default()),
+        recovery,
+        conflicts: Arc::new(ConflictQueue::default()),
     };
 
     // 创建系统托盘菜单
@@ -1051,6 +1546,15 @@ fn main() {
             remove_rule,
             update_rule,
             reorder_rules,
+            get_rule_sets,
+            add_rule_set,
+            update_rule_set,
+            remove_rule_set,
+            set_rule_set_enabled,
+            reorder_rule_sets,
+            list_rule_packs,
+            preview_rule_pack,
+            install_rule_pack,
             open_log_folder,
             read_log_by_date,
             cleanup_old_logs,
@@ -1064,6 +1568,8 @@ fn main() {
             remove_folder,
             toggle_folder,
             update_folder_rules,
+            update_folder_rule_sets,
+            get_folder_rules,
             start_monitoring,
             start_folder_monitoring,
             stop_monitoring,
@@ -1078,6 +1584,31 @@ fn main() {
             clear_activity_logs,
             export_config,
             import_config,
+            export_rules_file,
+            import_rules_file,
+            set_rules_file,
+            set_path_variable,
+            resolve_path,
+            get_run_history,
+            clear_run_history,
+            save_quarantine_settings,
+            save_preserve_settings,
+            save_copy_name_format,
+            list_quarantine,
+            restore_quarantined,
+            purge_quarantine,
+            list_trashed,
+            restore_trashed,
+            purge_trashed,
+            cancel_operation,
+            get_recovery_report,
+            list_conflicts,
+            resolve_conflict,
+            dismiss_conflict,
+            get_thumbnail,
+            clear_thumbnail_cache,
+            get_counter_values,
+            reset_counter,
             save_file,
             read_file,
             exit_app,
@@ -1092,6 +1623,40 @@ fn main() {
             let window = app.get_window("main").unwrap();
             let app_handle = app.handle();
             
+            // 恢复外部规则文件的热加载
+            let state = app.state::<AppState>();
+            let rules_file_path = state.config.lock().ok().and_then(|c| c.rules_file.clone());
+            if let Some(path) = rules_file_path {
+                match RulesFileWatcher::new(path.clone().into(), state.config.clone(), window.clone()) {
+                    Ok(watcher) => {
+                        if let Ok(mut guard) = state.rules_watcher.lock() {
+                            *guard = Some(watcher);
+                        }
+                        info!("规则文件热加载已启用: {}", path);
+                    }
+                    Err(e) => info!("启动规则文件监控失败: {}", e),
+                }
+            }
+            
+            // 复制进度通过 organize-progress 事件发送到前端
+            let progress_window = window.clone();
+            state.operations.set_emitter(Arc::new(move |event: &ProgressEvent| {
+                if let Err(e) = progress_window.emit("organize-progress", event) {
+                    info!("发送进度事件失败: {}", e);
+                }
+            }));
+            
+            // 冲突策略为询问时通过 conflict-pending 事件通知前端
+            let conflict_window = window.clone();
+            state.conflicts.set_emitter(Arc::new(move |conflict: &PendingConflict| {
+                if let Err(e) = conflict_window.emit("conflict-pending", conflict) {
+                    info!("发送冲突事件失败: {}", e);
+                }
+            }));
+            
+            // 定时清理隔离区中的过期文件
+            quarantine::start_purge_task(state.quarantine.clone(), state.config.clone());
+            
             // 先居中窗口（默认行为）
             info!("窗口居中显示");
             if let Err(e) = window.center() {
@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
+use crate::sanitize::SanitizePolicy;
 
 /// 文件信息结构
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -73,6 +74,20 @@ pub enum ConflictStrategy {
     Overwrite,
     /// 重命名为副本
     Rename,
+    /// 保留修改时间较新的一方（源文件较旧时跳过）
+    KeepNewer,
+    /// 保留较大的一方（源文件较小时跳过）
+    KeepLarger,
+    /// 内容相同时跳过，不同时重命名为副本
+    SkipIfIdentical,
+    /// 在文件名后添加时间（name_20261017-1530.ext）
+    AppendTimestamp,
+    /// 添加版本号（name.v2.ext、name.v3.ext……）
+    Versioned,
+    /// 放入待处理队列，由用户选择处理方式
+    Ask,
+    /// 文件夹合并到已存在的同名文件夹，其中的同名文件按规则的 merge_file_strategy 处理
+    Merge,
 }
 
 impl Default for ConflictStrategy {
@@ -96,6 +111,186 @@ pub enum RuleAction {
     
     /// 删除文件
     Delete,
+    
+    /// 解压压缩包（zip / tar / tar.gz / tar.xz）到指定目录
+    Extract {
+        destination: String,
+        /// 在目标目录中创建以压缩包命名的子文件夹
+        #[serde(default)]
+        create_subfolder: bool,
+        /// 压缩包内只有一个顶层文件夹时，直接解压该文件夹的内容
+        #[serde(default)]
+        flatten_single_root: bool,
+        /// 解压成功后将压缩包移到回收站
+        #[serde(default)]
+        trash_after: bool,
+    },
+    
+    /// 移动到指定目录，并在原位置留下指向新位置的符号链接
+    MoveAndLink { destination: String },
+    
+    /// 在指定目录中创建指向文件的符号链接（不移动原文件）
+    Symlink { destination: String },
+    
+    /// 在指定目录中创建文件的硬链接（不移动原文件，需位于同一分区）
+    Hardlink { destination: String },
+    
+    /// 运行外部程序（不经过 shell），参数支持 {path}、{dir} 及其他占位符
+    Exec {
+        program: String,
+        /// 参数列表，为空时只传递文件路径
+        #[serde(default)]
+        args: Vec<String>,
+        /// 工作目录（默认为文件所在目录）
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        working_dir: Option<String>,
+        #[serde(default = "default_exec_timeout")]
+        timeout_secs: u64,
+        /// 允许传递给程序的环境变量
+        #[serde(default)]
+        env: Vec<String>,
+        /// 视为成功的退出码
+        #[serde(default = "default_success_codes")]
+        success_codes: Vec<i32>,
+        /// 视为跳过的退出码（停止执行该规则的剩余步骤）
+        #[serde(default)]
+        skip_codes: Vec<i32>,
+    },
+    
+    /// 添加到压缩包（zip / tar.gz），压缩包路径支持模板，如 Archive/{year}-{month}.zip
+    Archive {
+        archive: String,
+        /// 保留原文件（默认在压缩包条目校验通过后删除原文件）
+        #[serde(default)]
+        keep_original: bool,
+    },
+    
+    /// 转换图片格式（png / jpeg / webp / bmp / tiff / gif），可按最大边长缩小
+    ///
+    /// 图片会重新编码，EXIF 等元数据不会保留。
+    ConvertImage {
+        destination: String,
+        format: String,
+        /// 长边最大像素，超过时等比缩小
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max_dimension: Option<u32>,
+        /// JPEG 质量（1-100，默认 85）；其他格式使用无损编码，不能设置
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        quality: Option<u8>,
+        /// 去除 EXIF、ICC 等元数据（目前只支持 true）
+        #[serde(default = "default_strip_metadata")]
+        strip_metadata: bool,
+        /// 转换成功后将原图移到回收站（默认保留）
+        #[serde(default)]
+        trash_original: bool,
+    },
+    
+    /// 清理文件名（非法字符、Windows 保留名、多余空白、Unicode 规范化、长度限制等）
+    Sanitize {
+        #[serde(default)]
+        policy: SanitizePolicy,
+    },
+}
+
+fn default_exec_timeout() -> u64 {
+    60
+}
+
+fn default_success_codes() -> Vec<i32> {
+    vec![0]
+}
+
+fn default_strip_metadata() -> bool {
+    true
+}
+
+impl RuleAction {
+    /// 动作类型名称（与配置中的 type 相同）
+    pub fn kind(&self) -> &'static str {
+        match self {
+            RuleAction::MoveTo { .. } => "MoveTo",
+            RuleAction::CopyTo { .. } => "CopyTo",
+            RuleAction::Rename { .. } => "Rename",
+            RuleAction::Delete => "Delete",
+            RuleAction::Extract { .. } => "Extract",
+            RuleAction::MoveAndLink { .. } => "MoveAndLink",
+            RuleAction::Symlink { .. } => "Symlink",
+            RuleAction::Hardlink { .. } => "Hardlink",
+            RuleAction::Exec { .. } => "Exec",
+            RuleAction::Archive { .. } => "Archive",
+            RuleAction::ConvertImage { .. } => "ConvertImage",
+            RuleAction::Sanitize { .. } => "Sanitize",
+        }
+    }
+
+    /// 是否为终结性动作（执行后文件离开原位置或被删除）
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            RuleAction::MoveTo { .. }
+                | RuleAction::MoveAndLink { .. }
+                | RuleAction::Delete
+                | RuleAction::Extract { trash_after: true, .. }
+                | RuleAction::Archive { keep_original: false, .. }
+                | RuleAction::ConvertImage { trash_original: true, .. }
+        )
+    }
+
+    /// 获取动作的目标路径模板（目标目录或压缩包路径）
+    pub fn destination(&self) -> Option<&str> {
+        match self {
+            RuleAction::MoveTo { destination }
+            | RuleAction::CopyTo { destination }
+            | RuleAction::MoveAndLink { destination }
+            | RuleAction::Symlink { destination }
+            | RuleAction::Hardlink { destination }
+            | RuleAction::Extract { destination, .. }
+            | RuleAction::Archive { archive: destination, .. }
+            | RuleAction::ConvertImage { destination, .. } => Some(destination),
+            RuleAction::Rename { .. }
+            | RuleAction::Delete
+            | RuleAction::Exec { .. }
+            | RuleAction::Sanitize { .. } => None,
+        }
+    }
+
+    /// 获取动作中的路径模板（目标目录或新文件名）
+    pub fn template(&self) -> Option<&str> {
+        match self {
+            RuleAction::Rename { pattern } => Some(pattern),
+            _ => self.destination(),
+        }
+    }
+
+    /// 以新的路径模板生成同类动作（其他选项保持不变）
+    pub fn with_template(&self, template: String) -> RuleAction {
+        let mut action = self.clone();
+        match &mut action {
+            RuleAction::MoveTo { destination }
+            | RuleAction::CopyTo { destination }
+            | RuleAction::MoveAndLink { destination }
+            | RuleAction::Symlink { destination }
+            | RuleAction::Hardlink { destination }
+            | RuleAction::Extract { destination, .. }
+            | RuleAction::Archive { archive: destination, .. }
+            | RuleAction::ConvertImage { destination, .. } => *destination = template,
+            RuleAction::Rename { pattern } => *pattern = template,
+            RuleAction::Delete | RuleAction::Exec { .. } | RuleAction::Sanitize { .. } => {}
+        }
+        action
+    }
+}
+
+/// 规则的周期性生效时间段
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActiveWindow {
+    /// 生效的星期（0=周日, 1=周一, ..., 6=周六），为空表示每天
+    #[serde(default)]
+    pub weekdays: Vec<u8>,
+    /// 开始时间，格式 "HH:MM"
+    pub start: String,
+    /// 结束时间，格式 "HH:MM"；早于开始时间表示跨越午夜（如 22:00-06:00）
+    pub end: String,
 }
 
 /// 整理规则
@@ -108,17 +303,79 @@ pub struct Rule {
     #[serde(default = "default_logic")]
     pub logic: String,
     pub conditions: Vec<RuleCondition>,
-    pub action: RuleAction,
+    /// 动作流水线：按顺序执行，后一步以前一步的输出路径作为输入，任一步失败即停止
+    #[serde(default)]
+    pub actions: Vec<RuleAction>,
     pub priority: i32,
     /// 文件冲突处理策略（默认为跳过）
     #[serde(default)]
     pub conflict_strategy: ConflictStrategy,
+    /// 匹配后是否继续评估后续规则（用于复制备份、记录清单等非破坏性规则）
+    #[serde(default)]
+    pub continue_matching: bool,
+    /// 副本文件名格式（覆盖全局设置，冲突策略需要保留两者时使用）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub copy_name_format: Option<String>,
+    /// 合并文件夹时同名文件的处理策略（不能为询问或合并，默认为跳过）
+    #[serde(default)]
+    pub merge_file_strategy: ConflictStrategy,
+    /// 生效开始时间（含），格式 YYYY-MM-DD 或 RFC 3339
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active_from: Option<String>,
+    /// 失效时间（YYYY-MM-DD 表示当天结束前有效），格式同上
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active_until: Option<String>,
+    /// 周期性生效时间段（为空表示任何时间都生效）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub active_windows: Vec<ActiveWindow>,
+    /// 旧版单一动作字段（仅用于迁移到 actions）
+    #[serde(default, skip_serializing)]
+    pub action: Option<RuleAction>,
+}
+
+impl Rule {
+    /// 动作流水线中是否包含终结性动作
+    pub fn has_terminal_action(&self) -> bool {
+        self.actions.iter().any(|a| a.is_terminal())
+    }
+
+    /// 迁移旧的单一 action 到 actions 流水线，返回是否发生了迁移
+    pub fn migrate_action(&mut self) -> bool {
+        match self.action.take() {
+            Some(action) => {
+                if self.actions.is_empty() {
+                    self.actions.push(action);
+                }
+                true
+            }
+            None => false,
+        }
+    }
 }
 
 fn default_logic() -> String {
     "and".to_string()
 }
 
+/// 规则集（可整体启用/禁用和排序的一组规则）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleSet {
+    pub id: String,
+    pub name: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// 规则集优先级（数值越小越先评估）
+    #[serde(default)]
+    pub priority: i32,
+    /// 包含的规则 ID 列表（同优先级规则按此顺序评估）
+    #[serde(default)]
+    pub rule_ids: Vec<String>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
 /// 文件事件
 #[allow(dead_code)]
 #[derive(Debug, Clone, Serialize)]
@@ -0,0 +1,117 @@
+use anyhow::Result;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::collections::BTreeMap;
+use std::env;
+use std::path::PathBuf;
+
+/// 命名占位符：{var:名称}、{xdg:目录}、{env:变量}
+static NAMED_PLACEHOLDER: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\{(var|xdg|env):([A-Za-z0-9_.\-]+)\}").expect("路径变量正则无效")
+});
+
+/// 环境变量简写：$NAME、${NAME}、%NAME%（不与正则捕获组 $1、${1} 冲突）
+static ENV_SHORTHAND: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\$\{([A-Za-z_][A-Za-z0-9_]*)\}|\$([A-Za-z_][A-Za-z0-9_]*)|%([A-Za-z_][A-Za-z0-9_]*)%")
+        .expect("环境变量正则无效")
+});
+
+/// 获取 XDG 用户目录（Windows / macOS 上为对应的系统目录）
+fn xdg_dir(name: &str) -> Option<PathBuf> {
+    match name.to_uppercase().as_str() {
+        "HOME" => dirs_next::home_dir(),
+        "DESKTOP" => dirs_next::desktop_dir(),
+        "DOCUMENTS" => dirs_next::document_dir(),
+        "DOWNLOAD" | "DOWNLOADS" => dirs_next::download_dir(),
+        "MUSIC" => dirs_next::audio_dir(),
+        "PICTURES" => dirs_next::picture_dir(),
+        "VIDEOS" => dirs_next::video_dir(),
+        "PUBLICSHARE" => dirs_next::public_dir(),
+        "TEMPLATES" => dirs_next::template_dir(),
+        _ => None,
+    }
+}
+
+/// 展开路径中的变量
+///
+/// 依次处理用户变量 `{var:名称}`（变量值中可以继续使用下面的写法）、
+/// 开头的 `~`、XDG 目录 `{xdg:DOCUMENTS}` 和环境变量 `{env:NAME}`。
+/// 未定义的命名占位符返回错误，避免把文件移动到字面名称的目录中；
+/// `$NAME` / `%NAME%` 简写在变量不存在时保持原样。
+/// 其他占位符（`{name}`、`{seq:...}`、`{recycle}` 等）不受影响。
+pub fn expand(path: &str, variables: &BTreeMap<String, String>) -> Result<String> {
+    let mut missing = Vec::new();
+
+    // 用户变量
+    let expanded = NAMED_PLACEHOLDER.replace_all(path, |caps: &regex::Captures| {
+        if &caps[1] != "var" {
+            return caps[0].to_string();
+        }
+        match variables.get(&caps[2]) {
+            Some(value) => value.clone(),
+            None => {
+                missing.push(caps[0].to_string());
+                caps[0].to_string()
+            }
+        }
+    });
+
+    // 用户主目录
+    let expanded = match expanded.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') || rest.starts_with('\\') => {
+            match dirs_next::home_dir() {
+                Some(home) => format!("{}{}", home.to_string_lossy(), rest),
+                None => {
+                    missing.push("~".to_string());
+                    expanded.to_string()
+                }
+            }
+        }
+        _ => expanded.to_string(),
+    };
+
+    // XDG 目录和环境变量
+    let expanded = NAMED_PLACEHOLDER.replace_all(&expanded, |caps: &regex::Captures| {
+        let value = match &caps[1] {
+            "xdg" => xdg_dir(&caps[2]).map(|p| p.to_string_lossy().to_string()),
+            "env" => env::var(&caps[2]).ok(),
+            _ => None,
+        };
+        value.unwrap_or_else(|| {
+            missing.push(caps[0].to_string());
+            caps[0].to_string()
+        })
+    });
+
+    let expanded = ENV_SHORTHAND.replace_all(&expanded, |caps: &regex::Captures| {
+        let name = caps.get(1).or_else(|| caps.get(2)).or_else(|| caps.get(3)).map_or("", |m| m.as_str());
+        env::var(name).unwrap_or_else(|_| caps[0].to_string())
+    });
+
+    if !missing.is_empty() {
+        anyhow::bail!("路径中包含未定义的变量: {}", missing.join(", "));
+    }
+
+    Ok(expanded.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_variables() {
+        let mut variables = BTreeMap::new();
+        variables.insert("archive_root".to_string(), "~/Archive".to_string());
+        let home = dirs_next::home_dir().unwrap().to_string_lossy().to_string();
+
+        assert_eq!(
+            expand("{var:archive_root}/{year}/$1", &variables).unwrap(),
+            format!("{}/Archive/{{year}}/$1", home)
+        );
+        assert_eq!(expand("{recycle}", &variables).unwrap(), "{recycle}");
+        assert_eq!(expand("~user/docs", &variables).unwrap(), "~user/docs");
+        assert!(expand("{var:missing}/x", &variables).is_err());
+        assert!(expand("{xdg:NOT_A_DIR}", &variables).is_err());
+    }
+}
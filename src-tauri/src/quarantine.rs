@@ -0,0 +1,280 @@
+use crate::config::AppConfig;
+use crate::file_ops;
+use crate::models::ConflictStrategy;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration as ChronoDuration, Local, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tracing::{error, info, warn};
+
+/// 每个隔离条目目录中的元数据文件名
+const META_FILE: &str = "entry.json";
+
+/// 每个隔离条目目录中存放原文件的子目录（与元数据分开，原文件同名时不会覆盖元数据）
+const DATA_DIR: &str = "data";
+
+/// 自动清理的检查间隔
+const PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// 同一毫秒内生成多个 ID 时使用的序号
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// 隔离区中的一个条目
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuarantineEntry {
+    pub id: String,
+    /// 删除前的完整路径（恢复时移回此处）
+    pub original_path: String,
+    pub name: String,
+    pub is_directory: bool,
+    pub size: u64,
+    pub quarantined_at: DateTime<Utc>,
+}
+
+/// FloatSort 管理的隔离区
+///
+/// 删除动作把文件移入隔离区而不是立即删除，保留期满后由定时任务彻底删除。
+/// 每个条目占用一个子目录，其中包含记录原路径的元数据和存放原文件的 data 目录。
+/// 未指定目录时隔离区不可用（用于预览和测试），删除动作直接删除文件。
+#[derive(Debug, Default)]
+pub struct Quarantine {
+    dir: Option<PathBuf>,
+    lock: Mutex<()>,
+}
+
+impl Quarantine {
+    pub fn open<P: AsRef<Path>>(dir: P) -> Self {
+        Self {
+            dir: Some(dir.as_ref().to_path_buf()),
+            lock: Mutex::new(()),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.dir.is_some()
+    }
+
+    /// 将文件或文件夹移入隔离区
+    pub fn put(&self, source: &Path) -> Result<QuarantineEntry> {
+        let dir = self.dir.as_ref().context("隔离区未启用")?;
+        let name = source
+            .file_name()
+            .and_then(|n| n.to_str())
+            .context("无法获取文件名")?
+            .to_string();
+        let metadata = fs::symlink_metadata(source)
+            .with_context(|| format!("无法读取文件信息: {:?}", source))?;
+
+        let now = Local::now();
+        let entry = QuarantineEntry {
+            id: format!("{}-{}", now.format("%Y%m%d%H%M%S%3f"), NEXT_ID.fetch_add(1, Ordering::Relaxed)),
+            original_path: source.to_string_lossy().to_string(),
+            name,
+            is_directory: metadata.is_dir(),
+            size: if metadata.is_dir() { dir_size(source) } else { metadata.len() },
+            quarantined_at: now.with_timezone(&Utc),
+        };
+
+        let _guard = self.lock.lock();
+        let entry_dir = dir.join(&entry.id);
+        let data_dir = entry_dir.join(DATA_DIR);
+        fs::create_dir_all(&data_dir)
+            .with_context(|| format!("无法创建隔离目录: {:?}", data_dir))?;
+
+        // 先写元数据再移动文件，避免出现无法恢复的条目
+        let result = fs::write(entry_dir.join(META_FILE), serde_json::to_string_pretty(&entry)?)
+            .map_err(anyhow::Error::from)
+            .and_then(|_| {
+                file_ops::move_file_with_strategy(source, &data_dir.to_string_lossy(), &ConflictStrategy::Overwrite.into())
+            });
+        if let Err(e) = result {
+            let _ = fs::remove_dir_all(&entry_dir);
+            return Err(e.context(format!("移入隔离区失败: {:?}", source)));
+        }
+
+        info!("已移入隔离区: {:?} ({})", source, entry.id);
+        Ok(entry)
+    }
+
+    /// 列出隔离区中的条目（最新的在前）
+    pub fn list(&self) -> Result<Vec<QuarantineEntry>> {
+        let dir = match &self.dir {
+            Some(d) if d.exists() => d,
+            _ => return Ok(Vec::new()),
+        };
+        let _guard = self.lock.lock();
+
+        let mut entries = Vec::new();
+        for item in fs::read_dir(dir).with_context(|| format!("无法读取隔离区: {:?}", dir))? {
+            let meta_path = item?.path().join(META_FILE);
+            let entry = fs::read_to_string(&meta_path)
+                .ok()
+                .and_then(|content| serde_json::from_str::<QuarantineEntry>(&content).ok());
+            match entry {
+                Some(entry) => entries.push(entry),
+                None => warn!("跳过无法识别的隔离条目: {:?}", meta_path),
+            }
+        }
+        entries.sort_by_key(|e| std::cmp::Reverse(e.quarantined_at));
+        Ok(entries)
+    }
+
+    /// 将条目恢复到原位置（原位置已有同名文件时自动重命名），返回恢复后的路径
+    pub fn restore(&self, id: &str) -> Result<String> {
+        let entry_dir = self.entry_dir(id)?;
+        let _guard = self.lock.lock();
+
+        let content = fs::read_to_string(entry_dir.join(META_FILE))
+            .with_context(|| format!("隔离条目不存在: {}", id))?;
+        let entry: QuarantineEntry = serde_json::from_str(&content)?;
+
+        let original = Path::new(&entry.original_path);
+        let parent = original.parent().context("无法获取原目录")?;
+        let restored = file_ops::move_file_with_strategy(
+            &entry_dir.join(DATA_DIR).join(&entry.name),
+            &parent.to_string_lossy(),
+            &ConflictStrategy::Rename.into(),
+        )?;
+        fs::remove_dir_all(&entry_dir)
+            .with_context(|| format!("无法删除隔离条目: {:?}", entry_dir))?;
+
+        info!("已从隔离区恢复: {} -> {}", id, restored);
+        Ok(restored)
+    }
+
+    /// 彻底删除一个条目
+    pub fn purge(&self, id: &str) -> Result<()> {
+        let entry_dir = self.entry_dir(id)?;
+        let _guard = self.lock.lock();
+        if !entry_dir.exists() {
+            anyhow::bail!("隔离条目不存在: {}", id);
+        }
+        fs::remove_dir_all(&entry_dir)
+            .with_context(|| format!("无法删除隔离条目: {:?}", entry_dir))?;
+        info!("已彻底删除隔离条目: {}", id);
+        Ok(())
+    }
+
+    /// 彻底删除早于指定时间的条目，`before` 为 None 时清空隔离区，返回删除的数量
+    pub fn purge_before(&self, before: Option<DateTime<Utc>>) -> Result<usize> {
+        let mut removed = 0;
+        for entry in self.list()? {
+            if before.is_some_and(|t| entry.quarantined_at >= t) {
+                continue;
+            }
+            match self.purge(&entry.id) {
+                Ok(()) => removed += 1,
+                Err(e) => warn!("清理隔离条目失败: {}", e),
+            }
+        }
+        Ok(removed)
+    }
+
+    /// 条目 ID 对应的目录（拒绝包含路径分隔符的 ID）
+    fn entry_dir(&self, id: &str) -> Result<PathBuf> {
+        let dir = self.dir.as_ref().context("隔离区未启用")?;
+        if id.is_empty() || id.contains(['/', '\\']) || id.contains("..") {
+            anyhow::bail!("无效的隔离条目 ID: {}", id);
+        }
+        Ok(dir.join(id))
+    }
+}
+
+/// 删除超过保留天数的条目（-1 表示永久保留）
+pub fn purge_expired(quarantine: &Quarantine, retention_days: i32) -> Result<usize> {
+    if retention_days < 0 {
+        return Ok(0);
+    }
+    let cutoff = Utc::now() - ChronoDuration::days(retention_days as i64);
+    let removed = quarantine.purge_before(Some(cutoff))?;
+    if removed > 0 {
+        info!("隔离区清理完成，删除了 {} 个过期条目", removed);
+    }
+    Ok(removed)
+}
+
+/// 启动隔离区定时清理（每小时按当前配置的保留天数检查一次）
+pub fn start_purge_task(quarantine: Arc<Quarantine>, config: Arc<Mutex<AppConfig>>) {
+    thread::spawn(move || loop {
+        let retention_days = match config.lock() {
+            Ok(c) => c.quarantine_retention_days,
+            Err(e) => {
+                error!("无法获取配置: {}", e);
+                return;
+            }
+        };
+        if let Err(e) = purge_expired(&quarantine, retention_days) {
+            warn!("隔离区清理失败: {}", e);
+        }
+        thread::sleep(PURGE_INTERVAL);
+    });
+}
+
+/// 递归计算文件夹大小
+fn dir_size(path: &Path) -> u64 {
+    let entries = match fs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(_) => return 0,
+    };
+    entries
+        .flatten()
+        .map(|entry| match entry.metadata() {
+            Ok(m) if m.is_dir() => dir_size(&entry.path()),
+            Ok(m) => m.len(),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_put_restore_purge() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        fs::create_dir_all(dir.join("docs/sub")).unwrap();
+        fs::write(dir.join("a.txt"), "hello").unwrap();
+        fs::write(dir.join("docs/sub/b.txt"), "abc").unwrap();
+
+        let quarantine = Quarantine::open(dir.join("quarantine"));
+        let file = quarantine.put(&dir.join("a.txt")).unwrap();
+        let folder = quarantine.put(&dir.join("docs")).unwrap();
+        assert!(!dir.join("a.txt").exists() && !dir.join("docs").exists());
+        assert_eq!((folder.is_directory, folder.size), (true, 3));
+        assert_eq!(quarantine.list().unwrap().len(), 2);
+
+        // 原位置被占用时恢复为副本名称
+        fs::write(dir.join("a.txt"), "new").unwrap();
+        let restored = quarantine.restore(&file.id).unwrap();
+        assert_ne!(Path::new(&restored), dir.join("a.txt"));
+        assert_eq!(fs::read_to_string(&restored).unwrap(), "hello");
+
+        assert!(quarantine.restore("../a.txt").is_err());
+        assert_eq!(purge_expired(&quarantine, 30).unwrap(), 0);
+        assert_eq!(purge_expired(&quarantine, 0).unwrap(), 1);
+        assert!(quarantine.list().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_file_named_like_metadata() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        fs::write(dir.join(META_FILE), "payload").unwrap();
+
+        let quarantine = Quarantine::open(dir.join("quarantine"));
+        let entry = quarantine.put(&dir.join(META_FILE)).unwrap();
+        assert_eq!(quarantine.list().unwrap().len(), 1);
+
+        let restored = quarantine.restore(&entry.id).unwrap();
+        assert_eq!(Path::new(&restored), dir.join(META_FILE));
+        assert_eq!(fs::read_to_string(&restored).unwrap(), "payload");
+        assert!(quarantine.list().unwrap().is_empty());
+    }
+}
@@ -1,8 +1,9 @@
-use crate::models::{FileInfo, Rule, RuleAction, RuleCondition};
+use crate::models::{ActiveWindow, FileInfo, Rule, RuleAction, RuleCondition};
+use crate::sanitize;
 use regex::Regex;
 use std::path::Path;
-use tracing::debug;
-use chrono::Utc;
+use tracing::{debug, warn};
+use chrono::{DateTime, Datelike, Local, NaiveDate, NaiveTime, TimeZone, Utc};
 
 /// 规则匹配结果
 #[derive(Debug)]
@@ -22,28 +23,113 @@ impl RuleEngine {
         Self { rules }
     }
 
-    /// 为文件查找匹配的规则（返回匹配结果，包含捕获组）
-    pub fn find_matching_rule<'a>(&'a self, file_info: &FileInfo) -> Option<RuleMatch<'a>> {
-        // 按优先级排序（已启用的规则）
+    /// 为文件查找所有应执行的规则（按优先级排序）
+    ///
+    /// 设置了 `continue_matching` 的规则匹配后继续评估后续规则，
+    /// 遇到第一个未设置该标志的匹配规则时停止。同一文件最多只接受一个
+    /// 包含终结性动作（移动、删除）的规则，之后再匹配到的此类规则会被忽略。
+    pub fn find_matching_rules<'a>(&'a self, file_info: &FileInfo) -> Vec<RuleMatch<'a>> {
+        self.find_matching_rules_at(file_info, Local::now())
+    }
+
+    /// 以指定的处理时间查找应执行的规则（不在生效时间内的规则会被跳过）
+    pub fn find_matching_rules_at<'a>(&'a self, file_info: &FileInfo, now: DateTime<Local>) -> Vec<RuleMatch<'a>> {
         let mut enabled_rules: Vec<&Rule> = self.rules
             .iter()
-            .filter(|r| r.enabled)
+            .filter(|r| r.enabled && Self::is_rule_active(r, now))
             .collect();
         
         enabled_rules.sort_by_key(|r| r.priority);
 
-        // 找到第一个匹配的规则
+        let mut matches = Vec::new();
+        let mut has_terminal = false;
+
         for rule in enabled_rules {
-            if let Some(captures) = self.check_conditions_with_captures(&rule.conditions, &rule.logic, file_info) {
-                debug!("文件 {} 匹配规则: {}", file_info.name, rule.name);
-                return Some(RuleMatch {
-                    rule,
-                    regex_captures: captures,
-                });
+            let captures = match self.check_conditions_with_captures(&rule.conditions, &rule.logic, file_info) {
+                Some(captures) => captures,
+                None => continue,
+            };
+
+            if rule.has_terminal_action() {
+                if has_terminal {
+                    warn!("文件 {} 已匹配移动/删除规则，忽略规则: {}", file_info.name, rule.name);
+                    if rule.continue_matching {
+                        continue;
+                    }
+                    break;
+                }
+                has_terminal = true;
+            }
+
+            debug!("文件 {} 匹配规则: {}", file_info.name, rule.name);
+            matches.push(RuleMatch {
+                rule,
+                regex_captures: captures,
+            });
+
+            if !rule.continue_matching {
+                break;
+            }
+        }
+
+        matches
+    }
+
+    /// 检查规则在指定时间是否处于生效期内
+    pub fn is_rule_active(rule: &Rule, now: DateTime<Local>) -> bool {
+        if let Some(from) = &rule.active_from {
+            match parse_active_time(from, false) {
+                Some(start) if now < start => return false,
+                Some(_) => {}
+                None => {
+                    warn!("规则 '{}' 的生效开始时间格式无效: {}", rule.name, from);
+                    return false;
+                }
             }
         }
 
-        None
+        if let Some(until) = &rule.active_until {
+            match parse_active_time(until, true) {
+                Some(end) if now > end => return false,
+                Some(_) => {}
+                None => {
+                    warn!("规则 '{}' 的失效时间格式无效: {}", rule.name, until);
+                    return false;
+                }
+            }
+        }
+
+        if rule.active_windows.is_empty() {
+            return true;
+        }
+
+        rule.active_windows.iter().any(|w| Self::is_in_window(w, now))
+    }
+
+    /// 检查时间是否落在周期性时间段内
+    fn is_in_window(window: &ActiveWindow, now: DateTime<Local>) -> bool {
+        let (start, end) = match (
+            NaiveTime::parse_from_str(&window.start, "%H:%M"),
+            NaiveTime::parse_from_str(&window.end, "%H:%M"),
+        ) {
+            (Ok(start), Ok(end)) => (start, end),
+            _ => {
+                warn!("无效的生效时间段: {} - {}", window.start, window.end);
+                return false;
+            }
+        };
+
+        let time = now.time();
+        let today = now.weekday().num_days_from_sunday() as u8;
+        let yesterday = (today + 6) % 7;
+        let day_ok = |day: u8| window.weekdays.is_empty() || window.weekdays.contains(&day);
+
+        if start < end {
+            day_ok(today) && time >= start && time < end
+        } else {
+            // 跨越午夜：星期以时间段开始的那一天为准
+            (day_ok(today) && time >= start) || (day_ok(yesterday) && time < end)
+        }
     }
 
     /// 检查所有条件是否满足，并返回正则表达式捕获组
@@ -223,8 +309,21 @@ impl RuleEngine {
     }
 
     /// 解析路径中的占位符
-    fn resolve_placeholders(&self, template: &str, file_info: &FileInfo, regex_captures: &[String]) -> String {
-        let mut result = template.to_string();
+    pub fn resolve_placeholders(&self, template: &str, file_info: &FileInfo, regex_captures: &[String]) -> String {
+        // 文件名（不含扩展名）
+        let name_without_ext = file_info.name
+            .strip_suffix(&format!(".{}", file_info.extension))
+            .unwrap_or(&file_info.name);
+        
+        // 带过滤器的占位符：{name|sanitize}、{ext|sanitize}、{1|sanitize}
+        let mut result = sanitize::render_filters(template, |key| match key {
+            "name" => Some(name_without_ext.to_string()),
+            "ext" => Some(file_info.extension.clone()),
+            _ => key
+                .parse::<usize>()
+                .ok()
+                .and_then(|i| regex_captures.get(i.checked_sub(1)?).cloned()),
+        });
         
         // 正则表达式捕获组 - 支持 $1, $2, $3... 或 ${1}, ${2}, ${3}...
         for (i, capture) in regex_captures.iter().enumerate() {
@@ -233,10 +332,6 @@ impl RuleEngine {
             result = result.replace(&format!("${{{}}}", index), capture);
         }
         
-        // 文件名（不含扩展名）
-        let name_without_ext = file_info.name
-            .strip_suffix(&format!(".{}", file_info.extension))
-            .unwrap_or(&file_info.name);
         result = result.replace("{name}", name_without_ext);
         
         // 扩展名
@@ -260,10 +355,44 @@ impl RuleEngine {
         result
     }
     
+    /// 解析外部命令参数中的占位符
+    ///
+    /// `{path}` 为完整路径，`{dir}` 为所在目录，直接替换为原值；其余部分与目标路径模板相同。
+    /// 路径中的花括号不会被当作占位符再次展开，解析出的值中的 `{path}`、`{dir}` 也不会被替换。
+    pub fn resolve_command_arg(&self, template: &str, file_info: &FileInfo, regex_captures: &[String]) -> String {
+        let dir = Path::new(&file_info.path)
+            .parent()
+            .unwrap_or(Path::new("."))
+            .to_string_lossy()
+            .to_string();
+        let tokens = [("{path}", file_info.path.as_str()), ("{dir}", dir.as_str())];
+
+        let mut result = String::new();
+        let mut rest = template;
+        while let Some((index, token, value)) = tokens
+            .iter()
+            .filter_map(|(token, value)| rest.find(token).map(|i| (i, *token, *value)))
+            .min_by_key(|(i, ..)| *i)
+        {
+            result.push_str(&self.resolve_placeholders(&rest[..index], file_info, regex_captures));
+            result.push_str(value);
+            rest = &rest[index + token.len()..];
+        }
+        result.push_str(&self.resolve_placeholders(rest, file_info, regex_captures));
+        result
+    }
+    
     /// 获取目标路径（支持正则捕获组）
     pub fn get_destination_path(&self, action: &RuleAction, file_info: &FileInfo, base_path: &Path, regex_captures: &[String]) -> Option<String> {
         match action {
-            RuleAction::MoveTo { destination } | RuleAction::CopyTo { destination } => {
+            RuleAction::MoveTo { destination }
+            | RuleAction::CopyTo { destination }
+            | RuleAction::MoveAndLink { destination }
+            | RuleAction::Symlink { destination }
+            | RuleAction::Hardlink { destination }
+            | RuleAction::Extract { destination, .. }
+            | RuleAction::Archive { archive: destination, .. }
+            | RuleAction::ConvertImage { destination, .. } => {
                 // 检查是否为回收站特殊路径
                 if destination == "{recycle}" {
                     return Some("{recycle}".to_string());
@@ -286,11 +415,30 @@ impl RuleEngine {
                 let parent = Path::new(&file_info.path).parent()?;
                 Some(parent.join(new_name).to_string_lossy().to_string())
             }
-            RuleAction::Delete => None,
+            RuleAction::Sanitize { policy } => {
+                let parent = Path::new(&file_info.path).parent()?;
+                Some(parent.join(sanitize::sanitize_file_name(&file_info.name, policy)).to_string_lossy().to_string())
+            }
+            RuleAction::Delete | RuleAction::Exec { .. } => None,
         }
     }
 }
 
+/// 解析生效时间：支持 RFC 3339 或 YYYY-MM-DD（`end_of_day` 为 true 时取当天结束时刻）
+pub fn parse_active_time(value: &str, end_of_day: bool) -> Option<DateTime<Local>> {
+    if let Ok(dt) = DateTime::parse_from_rfc3339(value) {
+        return Some(dt.with_timezone(&Local));
+    }
+
+    let date = NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()?;
+    let naive = if end_of_day {
+        date.and_hms_opt(23, 59, 59)?
+    } else {
+        date.and_hms_opt(0, 0, 0)?
+    };
+    Local.from_local_datetime(&naive).earliest()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -302,13 +450,22 @@ mod tests {
             id: "test".to_string(),
             name: "Test Rule".to_string(),
             enabled: true,
+            logic: "and".to_string(),
             conditions: vec![RuleCondition::Extension {
                 values: vec!["jpg".to_string(), "png".to_string()],
             }],
-            action: RuleAction::MoveTo {
+            actions: vec![RuleAction::MoveTo {
                 destination: "Images".to_string(),
-            },
+            }],
             priority: 1,
+            conflict_strategy: Default::default(),
+            continue_matching: false,
+            copy_name_format: None,
+            merge_file_strategy: Default::default(),
+            active_from: None,
+            active_until: None,
+            active_windows: vec![],
+            action: None,
         };
 
         let engine = RuleEngine::new(vec![rule]);
@@ -323,7 +480,109 @@ mod tests {
             is_directory: false,
         };
 
-        assert!(engine.find_matching_rule(&file_info).is_some());
+        assert!(!engine.find_matching_rules(&file_info).is_empty());
+    }
+
+    #[test]
+    fn test_command_arg_with_braces_in_name() {
+        let engine = RuleEngine::new(Vec::new());
+        let file_info = FileInfo {
+            path: "/in/{dir} {year}/a{path}.txt".to_string(),
+            name: "a{path}.txt".to_string(),
+            extension: "txt".to_string(),
+            size: 0,
+            created_at: None,
+            modified_at: None,
+            is_directory: false,
+        };
+
+        assert_eq!(engine.resolve_command_arg("{path}", &file_info, &[]), file_info.path);
+        assert_eq!(engine.resolve_command_arg("--out={dir}/{name}.{ext}", &file_info, &[]), "--out=/in/{dir} {year}/a{path}.txt");
+    }
+
+    #[test]
+    fn test_active_window_across_midnight() {
+        let mut rule = Rule {
+            id: "night".to_string(),
+            name: "night".to_string(),
+            enabled: true,
+            logic: "and".to_string(),
+            conditions: vec![],
+            actions: vec![],
+            priority: 0,
+            conflict_strategy: Default::default(),
+            continue_matching: false,
+            copy_name_format: None,
+            merge_file_strategy: Default::default(),
+            active_from: Some("2026-01-01".to_string()),
+            active_until: Some("2026-12-31".to_string()),
+            active_windows: vec![ActiveWindow {
+                weekdays: vec![5], // 周五晚上开始
+                start: "22:00".to_string(),
+                end: "06:00".to_string(),
+            }],
+            action: None,
+        };
+
+        let at = |s: &str| {
+            let naive = chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap();
+            Local.from_local_datetime(&naive).unwrap()
+        };
+        // 2026-10-16 是周五
+        assert!(RuleEngine::is_rule_active(&rule, at("2026-10-16 23:00")));
+        assert!(RuleEngine::is_rule_active(&rule, at("2026-10-17 05:59")));
+        assert!(!RuleEngine::is_rule_active(&rule, at("2026-10-17 23:00")));
+        assert!(!RuleEngine::is_rule_active(&rule, at("2026-10-16 12:00")));
+
+        rule.active_until = Some("2026-10-01".to_string());
+        assert!(!RuleEngine::is_rule_active(&rule, at("2026-10-16 23:00")));
+    }
+
+    #[test]
+    fn test_continue_matching_and_terminal_guard() {
+        let make_rule = |id: &str, priority: i32, continue_matching: bool, action: RuleAction| Rule {
+            id: id.to_string(),
+            name: id.to_string(),
+            enabled: true,
+            logic: "and".to_string(),
+            conditions: vec![RuleCondition::Extension {
+                values: vec!["jpg".to_string()],
+            }],
+            actions: vec![action],
+            priority,
+            conflict_strategy: Default::default(),
+            continue_matching,
+            copy_name_format: None,
+            merge_file_strategy: Default::default(),
+            active_from: None,
+            active_until: None,
+            active_windows: vec![],
+            action: None,
+        };
+
+        let engine = RuleEngine::new(vec![
+            make_rule("backup", 1, true, RuleAction::CopyTo { destination: "Backup".to_string() }),
+            make_rule("move_a", 2, true, RuleAction::MoveTo { destination: "A".to_string() }),
+            make_rule("move_b", 3, true, RuleAction::MoveTo { destination: "B".to_string() }),
+            make_rule("rename", 4, false, RuleAction::Rename { pattern: "{name}.{ext}".to_string() }),
+            make_rule("never", 5, false, RuleAction::Delete),
+        ]);
+
+        let file_info = FileInfo {
+            path: "photo.jpg".to_string(),
+            name: "photo.jpg".to_string(),
+            extension: "jpg".to_string(),
+            size: 1024,
+            created_at: None,
+            modified_at: None,
+            is_directory: false,
+        };
+
+        let ids: Vec<_> = engine.find_matching_rules(&file_info)
+            .iter()
+            .map(|m| m.rule.id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["backup", "move_a", "rename"]);
     }
 }
 
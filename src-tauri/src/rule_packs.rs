@@ -0,0 +1,335 @@
+use crate::config::AppConfig;
+use crate::file_ops::{self, OrganizeContext};
+use crate::models::{Rule, RuleSet};
+use crate::rule_engine::RuleEngine;
+use anyhow::{Context, Result};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use tracing::info;
+
+/// 内置规则包源文件（随程序一起编译）
+const BUILTIN_PACKS: &[&str] = &[
+    include_str!("../rule_packs/downloads_cleanup.json"),
+    include_str!("../rule_packs/screenshots.json"),
+    include_str!("../rule_packs/installers.json"),
+    include_str!("../rule_packs/archives.json"),
+    include_str!("../rule_packs/code_snippets.json"),
+    include_str!("../rule_packs/media_by_date.json"),
+];
+
+/// 内置规则包目录
+static CATALOG: Lazy<Vec<RulePack>> = Lazy::new(|| {
+    BUILTIN_PACKS
+        .iter()
+        .map(|source| serde_json::from_str(source).expect("内置规则包格式错误"))
+        .collect()
+});
+
+/// 规则包
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RulePack {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    /// 规则包版本（更新规则包时递增）
+    pub version: u32,
+    /// 包内规则（ID 在安装时会加上规则包前缀）
+    pub rules: Vec<Rule>,
+}
+
+/// 已安装规则包的记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstalledPack {
+    pub pack_id: String,
+    pub version: u32,
+    /// 安装时的规则快照，用于判断用户是否修改过规则
+    pub rules: Vec<Rule>,
+}
+
+/// 规则包目录项（供前端展示）
+#[derive(Debug, Clone, Serialize)]
+pub struct PackSummary {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub version: u32,
+    pub rule_count: usize,
+    /// 已安装的版本（未安装时为 None）
+    pub installed_version: Option<u32>,
+}
+
+/// 规则包预览结果
+#[derive(Debug, Clone, Serialize)]
+pub struct PackPreviewItem {
+    pub file_path: String,
+    pub rule_name: String,
+    pub target_path: Option<String>,
+}
+
+/// 规则包安装结果
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct InstallReport {
+    pub pack_id: String,
+    pub version: u32,
+    /// 新增的规则
+    pub added: Vec<String>,
+    /// 被新版本替换的规则
+    pub updated: Vec<String>,
+    /// 因用户修改过而保留的规则
+    pub kept: Vec<String>,
+    /// 新版本中已移除的规则
+    pub removed: Vec<String>,
+}
+
+/// 获取内置规则包目录
+pub fn catalog() -> &'static [RulePack] {
+    &CATALOG
+}
+
+/// 查找内置规则包
+pub fn find_pack(pack_id: &str) -> Option<&'static RulePack> {
+    CATALOG.iter().find(|p| p.id == pack_id)
+}
+
+/// 列出所有规则包及其安装状态
+pub fn list_packs(config: &AppConfig) -> Vec<PackSummary> {
+    CATALOG
+        .iter()
+        .map(|pack| PackSummary {
+            id: pack.id.clone(),
+            name: pack.name.clone(),
+            description: pack.description.clone(),
+            version: pack.version,
+            rule_count: pack.rules.len(),
+            installed_version: config
+                .installed_packs
+                .iter()
+                .find(|p| p.pack_id == pack.id)
+                .map(|p| p.version),
+        })
+        .collect()
+}
+
+/// 规则包对应的规则集 ID
+fn rule_set_id(pack_id: &str) -> String {
+    format!("pack.{}", pack_id)
+}
+
+/// 生成安装后的规则（ID 加上规则包前缀）
+fn installed_rules(pack: &RulePack) -> Vec<Rule> {
+    pack.rules
+        .iter()
+        .map(|rule| {
+            let mut rule = rule.clone();
+            rule.id = format!("pack.{}.{}", pack.id, rule.id);
+            rule
+        })
+        .collect()
+}
+
+/// 判断两条规则内容是否相同
+fn same_rule(a: &Rule, b: &Rule) -> bool {
+    serde_json::to_value(a).ok() == serde_json::to_value(b).ok()
+}
+
+/// 预览规则包对文件夹的整理效果（不实际操作文件）
+pub fn preview_pack(pack: &RulePack, folder_path: &Path) -> Result<Vec<PackPreviewItem>> {
+    let engine = RuleEngine::new(installed_rules(pack));
+    let mut items = Vec::new();
+
+    for entry in fs::read_dir(folder_path)
+        .with_context(|| format!("读取文件夹失败: {:?}", folder_path))?
+        .flatten()
+    {
+        let file_info = match file_ops::get_file_info(&entry.path()) {
+            Ok(info) => info,
+            Err(_) => continue,
+        };
+
+        let matches = engine.find_matching_rules(&file_info);
+        if matches.is_empty() {
+            continue;
+        }
+
+        items.push(PackPreviewItem {
+            file_path: file_info.path.clone(),
+            rule_name: matches
+                .iter()
+                .map(|m| m.rule.name.as_str())
+                .collect::<Vec<_>>()
+                .join(" → "),
+            target_path: file_ops::preview_matches(&matches, &file_info, &engine, &OrganizeContext::default()),
+        });
+    }
+
+    Ok(items)
+}
+
+/// 安装规则包到文件夹
+///
+/// 首次安装时添加规则和对应的规则集，并将规则集关联到文件夹。
+/// 已安装旧版本时合并更新：未被用户修改的规则替换为新版本，
+/// 修改过的规则保留，用户删除的规则不再恢复。
+pub fn install_pack(config: &mut AppConfig, pack: &RulePack, folder_id: &str) -> Result<InstallReport> {
+    if !config.folders.iter().any(|f| f.id == folder_id) {
+        anyhow::bail!("文件夹不存在");
+    }
+
+    let set_id = rule_set_id(&pack.id);
+    let new_rules = installed_rules(pack);
+    let mut report = InstallReport {
+        pack_id: pack.id.clone(),
+        version: pack.version,
+        ..Default::default()
+    };
+
+    let previous = config
+        .installed_packs
+        .iter()
+        .position(|p| p.pack_id == pack.id)
+        .map(|index| config.installed_packs.remove(index));
+
+    match &previous {
+        Some(installed) if installed.version >= pack.version => {
+            info!("规则包 {} 已是最新版本 (v{})", pack.id, installed.version);
+            config.installed_packs.push(installed.clone());
+        }
+        Some(installed) => {
+            merge_update(config, installed, &new_rules, &mut report);
+            config.installed_packs.push(InstalledPack {
+                pack_id: pack.id.clone(),
+                version: pack.version,
+                rules: new_rules.clone(),
+            });
+            info!("规则包 {} 已从 v{} 更新到 v{}", pack.id, installed.version, pack.version);
+        }
+        None => {
+            for rule in &new_rules {
+                if !config.rules.iter().any(|r| r.id == rule.id) {
+                    config.rules.push(rule.clone());
+                    report.added.push(rule.id.clone());
+                }
+            }
+            config.installed_packs.push(InstalledPack {
+                pack_id: pack.id.clone(),
+                version: pack.version,
+                rules: new_rules.clone(),
+            });
+            info!("规则包 {} (v{}) 已安装", pack.id, pack.version);
+        }
+    }
+
+    // 同步规则集：保留用户在规则集中的排序，追加新增规则，去掉已不存在的规则
+    let existing_ids: Vec<String> = config.rules.iter().map(|r| r.id.clone()).collect();
+    if let Some(rule_set) = config.rule_sets.iter_mut().find(|s| s.id == set_id) {
+        rule_set.rule_ids.retain(|id| existing_ids.contains(id));
+        for id in &report.added {
+            if !rule_set.rule_ids.contains(id) {
+                rule_set.rule_ids.push(id.clone());
+            }
+        }
+    } else {
+        let priority = config.rule_sets.iter().map(|s| s.priority).max().map_or(0, |p| p + 1);
+        config.rule_sets.push(RuleSet {
+            id: set_id.clone(),
+            name: pack.name.clone(),
+            enabled: true,
+            priority,
+            rule_ids: new_rules.iter()
+                .map(|r| r.id.clone())
+                .filter(|id| existing_ids.contains(id))
+                .collect(),
+        });
+    }
+
+    if let Some(folder) = config.folders.iter_mut().find(|f| f.id == folder_id) {
+        if !folder.rule_set_ids.contains(&set_id) {
+            folder.rule_set_ids.push(set_id);
+        }
+    }
+
+    Ok(report)
+}
+
+/// 将已安装的旧版本规则合并为新版本
+fn merge_update(config: &mut AppConfig, installed: &InstalledPack, new_rules: &[Rule], report: &mut InstallReport) {
+    for new_rule in new_rules {
+        let snapshot = installed.rules.iter().find(|r| r.id == new_rule.id);
+        let current = config.rules.iter().position(|r| r.id == new_rule.id);
+
+        match (snapshot, current) {
+            // 用户未修改过：替换为新版本
+            (Some(old), Some(index)) if same_rule(old, &config.rules[index]) => {
+                if !same_rule(old, new_rule) {
+                    config.rules[index] = new_rule.clone();
+                    report.updated.push(new_rule.id.clone());
+                }
+            }
+            // 用户修改过：保留用户版本
+            (Some(_), Some(_)) => report.kept.push(new_rule.id.clone()),
+            // 用户删除过：不再恢复
+            (Some(_), None) => {}
+            // 新版本新增的规则
+            (None, Some(_)) => report.kept.push(new_rule.id.clone()),
+            (None, None) => {
+                config.rules.push(new_rule.clone());
+                report.added.push(new_rule.id.clone());
+            }
+        }
+    }
+
+    // 新版本中已移除的规则：未修改过的一并移除
+    for old in &installed.rules {
+        if new_rules.iter().any(|r| r.id == old.id) {
+            continue;
+        }
+        if let Some(index) = config.rules.iter().position(|r| r.id == old.id) {
+            if same_rule(old, &config.rules[index]) {
+                config.rules.remove(index);
+                report.removed.push(old.id.clone());
+            } else {
+                report.kept.push(old.id.clone());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_packs_are_valid() {
+        let mut ids = std::collections::HashSet::new();
+        for pack in catalog() {
+            assert!(ids.insert(pack.id.as_str()), "重复的规则包 ID: {}", pack.id);
+            assert!(!pack.rules.is_empty(), "规则包 {} 没有规则", pack.id);
+            for rule in &pack.rules {
+                assert!(!rule.actions.is_empty(), "规则 {} 没有动作", rule.id);
+            }
+        }
+    }
+
+    #[test]
+    fn test_pack_applies_only_to_its_folder() {
+        let mut config = AppConfig::default();
+        for id in ["a", "b"] {
+            let folder = serde_json::json!({ "id": id, "path": format!("/tmp/{}", id), "name": id, "enabled": true, "rule_ids": [] });
+            config.folders.push(serde_json::from_value(folder).unwrap());
+        }
+        let pack = &catalog()[0];
+        install_pack(&mut config, pack, "a").unwrap();
+
+        let ids = |rules: Vec<Rule>| rules.into_iter().map(|r| r.id).collect::<Vec<_>>();
+        let pack_rules = ids(installed_rules(pack));
+        let in_a = ids(config.rules_for_folder(&config.folders[0]));
+        let in_b = ids(config.rules_for_folder(&config.folders[1]));
+        assert!(pack_rules.iter().all(|id| in_a.contains(id)));
+        assert!(pack_rules.iter().all(|id| !in_b.contains(id)));
+        assert!(pack_rules.iter().all(|id| !ids(config.active_rules()).contains(id)));
+        // 未关联任何规则的文件夹仍使用全局规则
+        assert!(!in_b.is_empty());
+    }
+}
@@ -0,0 +1,429 @@
+use crate::config::{AppConfig, WatchFolder};
+use crate::counters::SequenceCounter;
+use crate::image_convert::ConvertOptions;
+use crate::models::{Rule, RuleAction, RuleCondition, RuleSet};
+use crate::rule_engine::parse_active_time;
+use anyhow::{Context, Result};
+use chrono::NaiveTime;
+use notify::{Config, Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::channel;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tauri::Window;
+use toml_edit::{ArrayOfTables, DocumentMut, Item, RawString, Table};
+use tracing::{error, info, warn};
+
+/// 规则文件内容（可手工编辑、纳入版本管理）
+///
+/// 未出现的部分在导入时保持现有配置不变。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RulesFile {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rules: Option<Vec<Rule>>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rule_sets: Option<Vec<RuleSet>>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub folders: Option<Vec<WatchFolder>>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub counters: Option<Vec<SequenceCounter>>,
+}
+
+/// 规则文件格式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RulesFormat {
+    Toml,
+    Yaml,
+}
+
+impl RulesFormat {
+    /// 根据扩展名判断文件格式
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let ext = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("")
+            .to_lowercase();
+
+        match ext.as_str() {
+            "toml" => Ok(RulesFormat::Toml),
+            "yaml" | "yml" => Ok(RulesFormat::Yaml),
+            _ => anyhow::bail!("不支持的规则文件格式（仅支持 .toml / .yaml / .yml）: {:?}", path),
+        }
+    }
+}
+
+impl RulesFile {
+    /// 从配置中提取规则、规则集和文件夹
+    pub fn from_config(config: &AppConfig) -> Self {
+        Self {
+            rules: Some(config.rules.clone()),
+            rule_sets: Some(config.rule_sets.clone()),
+            folders: Some(config.folders.clone()),
+            counters: Some(config.counters.clone()),
+        }
+    }
+
+    /// 解析规则文件内容
+    pub fn parse(content: &str, format: RulesFormat) -> Result<Self> {
+        let mut file: RulesFile = match format {
+            RulesFormat::Toml => toml::from_str(content).context("TOML 格式错误")?,
+            RulesFormat::Yaml => serde_yaml_ng::from_str(content).context("YAML 格式错误")?,
+        };
+
+        // 兼容旧版单一 action 写法
+        for rule in file.rules.iter_mut().flatten() {
+            rule.migrate_action();
+        }
+
+        Ok(file)
+    }
+
+    /// 序列化为规则文件内容
+    pub fn render(&self, format: RulesFormat) -> Result<String> {
+        match format {
+            RulesFormat::Toml => toml::to_string_pretty(self).context("生成 TOML 失败"),
+            RulesFormat::Yaml => serde_yaml_ng::to_string(self).context("生成 YAML 失败"),
+        }
+    }
+
+    /// 从文件加载
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let format = RulesFormat::from_path(path)?;
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("无法读取规则文件: {:?}", path))?;
+        Self::parse(&content, format).with_context(|| format!("规则文件解析失败: {:?}", path))
+    }
+
+    /// 导出到文件
+    ///
+    /// 会保留已有文件开头的注释块（文件说明、维护说明等）。TOML 格式还会按 ID
+    /// 保留每条规则、规则集、文件夹和计数器中的注释；YAML 格式只保留开头的注释块。
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        let format = RulesFormat::from_path(path)?;
+
+        let previous = fs::read_to_string(path).unwrap_or_default();
+        let header = leading_comments(&previous);
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut rendered = self.render(format)?;
+        if format == RulesFormat::Toml {
+            rendered = keep_toml_comments(&previous, &rendered, &header);
+        }
+        let content = format!("{}{}", header, rendered);
+        fs::write(path, content).with_context(|| format!("无法写入规则文件: {:?}", path))?;
+
+        info!("规则文件已导出到 {:?}", path);
+        Ok(())
+    }
+
+    /// 将规则文件内容应用到配置（返回新配置，未出现的部分保持不变）
+    pub fn apply_to(&self, config: &AppConfig) -> AppConfig {
+        let mut config = config.clone();
+        if let Some(rules) = &self.rules {
+            config.rules = rules.clone();
+        }
+        if let Some(rule_sets) = &self.rule_sets {
+            config.rule_sets = rule_sets.clone();
+        }
+        if let Some(counters) = &self.counters {
+            config.counters = counters.clone();
+        }
+        if let Some(folders) = &self.folders {
+            config.folders = folders.clone();
+            for folder in config.folders.iter_mut() {
+                folder.migrate_processing_mode();
+            }
+        }
+        config
+    }
+}
+
+/// 提取文件开头的注释块（TOML 和 YAML 都使用 # 注释）
+fn leading_comments(content: &str) -> String {
+    let mut header = String::new();
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('#') || trimmed.is_empty() {
+            header.push_str(line);
+            header.push('\n');
+        } else {
+            break;
+        }
+    }
+
+    // 只有空行时不保留
+    if header.trim().is_empty() {
+        String::new()
+    } else {
+        header
+    }
+}
+
+/// 将已有 TOML 文件中的注释转移到新生成的内容中
+///
+/// 规则、规则集和文件夹按 `id` 对应，计数器按 `name` 对应，其余按顺序对应；
+/// 已有文件无法解析时直接返回新内容。`header` 已单独保留，不会重复。
+fn keep_toml_comments(previous: &str, rendered: &str, header: &str) -> String {
+    let (Ok(old), Ok(mut new)) = (previous.parse::<DocumentMut>(), rendered.parse::<DocumentMut>()) else {
+        return rendered.to_string();
+    };
+    copy_table_comments(old.as_table(), new.as_table_mut(), header);
+    new.to_string()
+}
+
+fn copy_table_comments(old: &Table, new: &mut Table, header: &str) {
+    if let Some(prefix) = comment(old.decor().prefix(), header) {
+        new.decor_mut().set_prefix(prefix);
+    }
+
+    for (key, old_item) in old.iter() {
+        let Some(new_item) = new.get_mut(key) else {
+            continue;
+        };
+        match (old_item, new_item) {
+            (Item::Table(old), Item::Table(new)) => copy_table_comments(old, new, header),
+            (Item::ArrayOfTables(old), Item::ArrayOfTables(new)) => {
+                for (index, table) in new.iter_mut().enumerate() {
+                    if let Some(old) = matching_table(old, table, index) {
+                        copy_table_comments(old, table, header);
+                    }
+                }
+            }
+            (Item::Value(old), Item::Value(new)) => {
+                if let Some(suffix) = comment(old.decor().suffix(), header) {
+                    new.decor_mut().set_suffix(suffix);
+                }
+            }
+            _ => {}
+        }
+
+        // 字段前的注释
+        if let (Some(prefix), Some(mut new_key)) = (old.key(key).and_then(|k| comment(k.leaf_decor().prefix(), header)), new.key_mut(key)) {
+            new_key.leaf_decor_mut().set_prefix(prefix);
+        }
+    }
+}
+
+/// 在已有文件的表数组中查找对应的表（按 id 或 name，没有时按位置）
+fn matching_table<'a>(old: &'a ArrayOfTables, new: &Table, index: usize) -> Option<&'a Table> {
+    for field in ["id", "name"] {
+        if let Some(value) = new.get(field).and_then(Item::as_str) {
+            return old.iter().find(|t| t.get(field).and_then(Item::as_str) == Some(value));
+        }
+    }
+    old.get(index)
+}
+
+/// 含有注释的修饰文本（去掉已单独保留的开头注释块）
+fn comment<'a>(raw: Option<&'a RawString>, header: &str) -> Option<&'a str> {
+    let text = raw.and_then(RawString::as_str)?;
+    Some(text.strip_prefix(header).unwrap_or(text)).filter(|s| s.contains('#'))
+}
+
+/// 校验配置中的规则和规则集（`check_folders` 为 true 时同时校验文件夹），返回所有发现的问题
+pub fn validate(config: &AppConfig, check_folders: bool) -> Vec<String> {
+    let mut errors = Vec::new();
+
+    let mut rule_ids = HashSet::new();
+    for rule in &config.rules {
+        if !rule_ids.insert(rule.id.as_str()) {
+            errors.push(format!("规则 ID 重复: {}", rule.id));
+        }
+        if rule.actions.is_empty() {
+            errors.push(format!("规则 '{}' 没有配置任何动作", rule.id));
+        }
+        for value in rule.active_from.iter().chain(rule.active_until.iter()) {
+            if parse_active_time(value, false).is_none() {
+                errors.push(format!("规则 '{}' 的生效时间格式无效: {}", rule.id, value));
+            }
+        }
+        for window in &rule.active_windows {
+            let valid = NaiveTime::parse_from_str(&window.start, "%H:%M").is_ok()
+                && NaiveTime::parse_from_str(&window.end, "%H:%M").is_ok()
+                && window.weekdays.iter().all(|d| *d <= 6);
+            if !valid {
+                errors.push(format!("规则 '{}' 的生效时间段无效: {} - {}", rule.id, window.start, window.end));
+            }
+        }
+        for action in &rule.actions {
+            if let Some(destination) = action.destination() {
+                if let Err(e) = config.expand_path(destination) {
+                    errors.push(format!("规则 '{}' 的目标路径无效: {}", rule.id, e));
+                }
+            }
+            if let RuleAction::ConvertImage { format, max_dimension, quality, strip_metadata, .. } = action {
+                if let Err(e) = ConvertOptions::new(format, *max_dimension, *quality, *strip_metadata) {
+                    errors.push(format!("规则 '{}' 的图片转换设置无效: {}", rule.id, e));
+                }
+            }
+        }
+        for condition in &rule.conditions {
+            if let RuleCondition::NameRegex { pattern } = condition {
+                if let Err(e) = Regex::new(pattern) {
+                    errors.push(format!("规则 '{}' 的正则表达式无效: {}", rule.id, e));
+                }
+            }
+        }
+    }
+
+    let mut set_ids = HashSet::new();
+    for rule_set in &config.rule_sets {
+        if !set_ids.insert(rule_set.id.as_str()) {
+            errors.push(format!("规则集 ID 重复: {}", rule_set.id));
+        }
+        for id in &rule_set.rule_ids {
+            if !rule_ids.contains(id.as_str()) {
+                errors.push(format!("规则集 '{}' 引用了不存在的规则: {}", rule_set.id, id));
+            }
+        }
+    }
+
+    if !check_folders {
+        return errors;
+    }
+
+    let mut folder_ids = HashSet::new();
+    for folder in &config.folders {
+        if !folder_ids.insert(folder.id.as_str()) {
+            errors.push(format!("文件夹 ID 重复: {}", folder.id));
+        }
+        for id in &folder.rule_ids {
+            if !rule_ids.contains(id.as_str()) {
+                errors.push(format!("文件夹 '{}' 引用了不存在的规则: {}", folder.id, id));
+            }
+        }
+        for id in &folder.rule_set_ids {
+            if !set_ids.contains(id.as_str()) {
+                errors.push(format!("文件夹 '{}' 引用了不存在的规则集: {}", folder.id, id));
+            }
+        }
+    }
+
+    errors
+}
+
+/// 加载规则文件、校验并生成应用后的新配置
+pub fn load_and_apply(path: &Path, config: &AppConfig) -> Result<AppConfig> {
+    let file = RulesFile::load(path)?;
+    let new_config = file.apply_to(config);
+
+    // 规则文件未包含文件夹时，不因现有文件夹配置中的问题拒绝加载
+    let errors = validate(&new_config, file.folders.is_some());
+    if !errors.is_empty() {
+        anyhow::bail!("规则文件校验失败:\n{}", errors.join("\n"));
+    }
+
+    Ok(new_config)
+}
+
+/// 规则文件监控器：文件变化时自动校验并热加载
+pub struct RulesFileWatcher {
+    _watcher: Arc<Mutex<RecommendedWatcher>>, // 保持 watcher 存活
+}
+
+impl RulesFileWatcher {
+    /// 创建并启动规则文件监控
+    pub fn new(path: PathBuf, config: Arc<Mutex<AppConfig>>, window: Window) -> Result<Self, String> {
+        RulesFormat::from_path(&path).map_err(|e| e.to_string())?;
+
+        // 编辑器通常以“写临时文件再替换”的方式保存，因此监控所在目录并按文件名过滤
+        let dir = path
+            .parent()
+            .map(Path::to_path_buf)
+            .ok_or_else(|| "无法获取规则文件所在目录".to_string())?;
+        let file_name = path
+            .file_name()
+            .map(|n| n.to_os_string())
+            .ok_or_else(|| "无法获取规则文件名".to_string())?;
+
+        let (tx, rx) = channel();
+        let mut watcher = RecommendedWatcher::new(
+            move |res: Result<Event, notify::Error>| {
+                if let Ok(event) = res {
+                    let _ = tx.send(event);
+                }
+            },
+            Config::default(),
+        )
+        .map_err(|e| e.to_string())?;
+
+        watcher
+            .watch(&dir, RecursiveMode::NonRecursive)
+            .map_err(|e| format!("无法监控规则文件目录 {:?}: {}", dir, e))?;
+        info!("开始监控规则文件: {:?}", path);
+
+        thread::spawn(move || {
+            while let Ok(event) = rx.recv() {
+                let relevant = matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_))
+                    && event.paths.iter().any(|p| p.file_name() == Some(file_name.as_os_str()));
+                if !relevant {
+                    continue;
+                }
+
+                // 合并短时间内的连续事件（编辑器保存时通常会触发多次）
+                thread::sleep(Duration::from_millis(300));
+                while rx.try_recv().is_ok() {}
+
+                Self::reload(&path, &config, &window);
+            }
+            info!("规则文件监控线程已结束");
+        });
+
+        Ok(Self {
+            _watcher: Arc::new(Mutex::new(watcher)),
+        })
+    }
+
+    /// 重新加载规则文件，校验失败时保留当前规则
+    fn reload(path: &Path, config: &Arc<Mutex<AppConfig>>, window: &Window) {
+        let mut config = match config.lock() {
+            Ok(c) => c,
+            Err(e) => {
+                error!("无法获取配置: {}", e);
+                return;
+            }
+        };
+
+        match load_and_apply(path, &config) {
+            Ok(new_config) => {
+                *config = new_config;
+                if let Err(e) = config.save_to_file("data/config.json") {
+                    error!("保存热加载后的配置失败: {}", e);
+                }
+                info!("规则文件已热加载: {:?} ({} 条规则)", path, config.rules.len());
+
+                let _ = window.emit(
+                    "rules-file-reloaded",
+                    serde_json::json!({
+                        "path": path.to_string_lossy().to_string(),
+                        "rules_count": config.rules.len(),
+                    }),
+                );
+            }
+            Err(e) => {
+                warn!("规则文件热加载失败，保留当前规则: {:#}", e);
+                let _ = window.emit(
+                    "rules-file-error",
+                    serde_json::json!({
+                        "path": path.to_string_lossy().to_string(),
+                        "error": format!("{:#}", e),
+                    }),
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,255 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use unicode_normalization::UnicodeNormalization;
+
+/// 模板过滤器：{name|sanitize}、{ext|sanitize}、{1|sanitize}（正则捕获组）
+static SANITIZE_FILTER: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\{([A-Za-z0-9_]+)\|sanitize\}").expect("过滤器正则无效")
+});
+
+/// Windows 文件名中不允许出现的字符
+const WINDOWS_RESERVED_CHARS: &[char] = &['<', '>', ':', '"', '/', '\\', '|', '?', '*'];
+
+/// Windows 保留的设备名（不区分大小写，带扩展名也不允许）
+const WINDOWS_RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8", "COM9",
+    "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Unicode 规范化形式
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum UnicodeForm {
+    /// 不做规范化
+    None,
+    /// 合成形式（macOS 产生的分解字符会被合并）
+    #[default]
+    Nfc,
+    /// 兼容合成形式（全角字母、数字和标点转为半角）
+    Nfkc,
+}
+
+/// 文件名清理策略
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct SanitizePolicy {
+    /// 删除控制字符
+    pub strip_control: bool,
+    /// 替换 Windows 不允许的字符和设备名，并去掉结尾的点和空格
+    pub windows_safe: bool,
+    /// 替换非法字符时使用的字符串
+    pub replacement: String,
+    /// 合并连续空白并去掉首尾空白
+    pub collapse_whitespace: bool,
+    pub normalize: UnicodeForm,
+    /// 删除 emoji 和其他图形符号
+    pub strip_emoji: bool,
+    /// 去掉重复的扩展名（report.pdf.pdf -> report.pdf）
+    pub dedupe_extension: bool,
+    pub lowercase_extension: bool,
+    /// 文件名最大字节数（UTF-8），0 表示不限制
+    pub max_bytes: usize,
+}
+
+impl Default for SanitizePolicy {
+    fn default() -> Self {
+        Self {
+            strip_control: true,
+            windows_safe: true,
+            replacement: "_".to_string(),
+            collapse_whitespace: true,
+            normalize: UnicodeForm::Nfc,
+            strip_emoji: false,
+            dedupe_extension: true,
+            lowercase_extension: true,
+            max_bytes: 255,
+        }
+    }
+}
+
+/// 清理完整文件名（分别处理主文件名和扩展名）
+pub fn sanitize_file_name(name: &str, policy: &SanitizePolicy) -> String {
+    // 以点开头且没有其他点的文件（如 .gitignore）视为没有扩展名
+    let (stem, extension) = match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.trim().is_empty() => (stem, Some(ext)),
+        _ => (name, None),
+    };
+
+    let mut extension = extension.map(|ext| {
+        let ext = clean(ext, policy).replace(' ', "");
+        if policy.lowercase_extension {
+            ext.to_lowercase()
+        } else {
+            ext
+        }
+    });
+    if extension.as_deref() == Some("") {
+        extension = None;
+    }
+
+    let mut stem = clean(stem, policy);
+    if policy.dedupe_extension {
+        if let Some(ext) = &extension {
+            let suffix = format!(".{}", ext.to_lowercase());
+            while stem.len() > suffix.len() && stem.to_lowercase().ends_with(&suffix) {
+                stem.truncate(stem.len() - suffix.len());
+                stem = finish(stem, policy);
+            }
+        }
+    }
+
+    // 扩展名最多占用一半长度，其余留给主文件名
+    if policy.max_bytes > 0 {
+        if let Some(ext) = &mut extension {
+            truncate_bytes(ext, policy.max_bytes / 2);
+        }
+        let ext_len = extension.as_ref().map_or(0, |e| e.len() + 1);
+        truncate_bytes(&mut stem, policy.max_bytes.saturating_sub(ext_len));
+        stem = finish(stem, policy);
+    }
+
+    if stem.is_empty() {
+        stem = fallback(policy);
+    }
+    if policy.windows_safe && is_reserved_name(&stem) {
+        stem.push_str(&fallback(policy));
+    }
+
+    match extension {
+        Some(ext) => format!("{}.{}", stem, ext),
+        None => stem,
+    }
+}
+
+/// 清理模板中的一个片段（不处理扩展名，不限制长度）
+pub fn sanitize_component(text: &str, policy: &SanitizePolicy) -> String {
+    let text = clean(text, policy);
+    if policy.windows_safe && is_reserved_name(&text) {
+        return text + &fallback(policy);
+    }
+    text
+}
+
+/// 替换模板中的 `{key|sanitize}` 过滤器，`resolve` 返回 key 对应的原始值
+pub fn render_filters<F>(template: &str, resolve: F) -> String
+where
+    F: Fn(&str) -> Option<String>,
+{
+    if !template.contains("|sanitize}") {
+        return template.to_string();
+    }
+    let policy = SanitizePolicy::default();
+    SANITIZE_FILTER
+        .replace_all(template, |caps: &regex::Captures| match resolve(&caps[1]) {
+            Some(value) => sanitize_component(&value, &policy),
+            None => caps[0].to_string(),
+        })
+        .to_string()
+}
+
+/// 逐字符清理并规范空白
+fn clean(text: &str, policy: &SanitizePolicy) -> String {
+    let normalized: String = match policy.normalize {
+        UnicodeForm::None => text.to_string(),
+        UnicodeForm::Nfc => text.nfc().collect(),
+        UnicodeForm::Nfkc => text.nfkc().collect(),
+    };
+
+    let mut result = String::with_capacity(normalized.len());
+    for c in normalized.chars() {
+        // 制表符、换行等空白控制字符按空格处理
+        if c.is_control() && c.is_whitespace() {
+            result.push(' ');
+            continue;
+        }
+        if c == '\0' || (policy.strip_control && c.is_control()) {
+            continue;
+        }
+        if policy.strip_emoji && is_emoji(c) {
+            continue;
+        }
+        // 路径分隔符始终替换，否则文件会被写到其他目录
+        if c == '/' || (policy.windows_safe && (WINDOWS_RESERVED_CHARS.contains(&c) || c.is_control())) {
+            result.push_str(&policy.replacement);
+            continue;
+        }
+        result.push(c);
+    }
+
+    finish(result, policy)
+}
+
+/// 合并空白，Windows 下去掉结尾的点和空格
+fn finish(text: String, policy: &SanitizePolicy) -> String {
+    let mut text = if policy.collapse_whitespace {
+        text.split_whitespace().collect::<Vec<_>>().join(" ")
+    } else {
+        text
+    };
+    if policy.windows_safe {
+        let trimmed = text.trim_end_matches(['.', ' ']).len();
+        text.truncate(trimmed);
+    }
+    text
+}
+
+/// 按字节截断，不拆分多字节字符
+fn truncate_bytes(text: &mut String, max_bytes: usize) {
+    if text.len() <= max_bytes {
+        return;
+    }
+    let mut end = max_bytes;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    text.truncate(end);
+}
+
+fn is_reserved_name(stem: &str) -> bool {
+    // "CON.tar" 这类名称在 Windows 上同样不可用
+    let base = stem.split('.').next().unwrap_or(stem).trim_end();
+    WINDOWS_RESERVED_NAMES.iter().any(|r| r.eq_ignore_ascii_case(base))
+}
+
+fn fallback(policy: &SanitizePolicy) -> String {
+    if policy.replacement.is_empty() {
+        "_".to_string()
+    } else {
+        policy.replacement.clone()
+    }
+}
+
+/// 常见 emoji 和图形符号所在的 Unicode 区段
+fn is_emoji(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x1F000..=0x1FAFF | 0x2600..=0x27BF | 0x2B00..=0x2BFF | 0xFE0F | 0x200D
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitize_file_name() {
+        let policy = SanitizePolicy::default();
+        assert_eq!(sanitize_file_name("  draft   v2 .PDF", &policy), "draft v2.pdf");
+        assert_eq!(sanitize_file_name("report.pdf.PDF", &policy), "report.pdf");
+        assert_eq!(sanitize_file_name("a<b>:c?.txt", &policy), "a_b__c_.txt");
+        assert_eq!(sanitize_file_name("con.txt", &policy), "con_.txt");
+        assert_eq!(sanitize_file_name("tab\there\u{7}.md", &policy), "tab here.md");
+        assert_eq!(sanitize_file_name("notes. . .", &policy), "notes");
+        assert_eq!(sanitize_file_name(".gitignore", &policy), ".gitignore");
+
+        let emoji = SanitizePolicy { strip_emoji: true, ..SanitizePolicy::default() };
+        assert_eq!(sanitize_file_name("party 🎉 time.jpg", &emoji), "party time.jpg");
+
+        let short = SanitizePolicy { max_bytes: 10, ..SanitizePolicy::default() };
+        assert_eq!(sanitize_file_name("文件名很长.txt", &short), "文件.txt");
+
+        let rendered = render_filters("{name|sanitize}/{year}", |key| (key == "name").then(|| "a:b ".to_string()));
+        assert_eq!(rendered, "a_b/{year}");
+    }
+}
@@ -190,16 +190,15 @@ impl Scheduler {
     /// 扫描文件夹并向前端发送文件检测事件
     fn scan_and_emit_folder(
         folder: &crate::config::WatchFolder,
-        _config: &AppConfig,
+        config: &AppConfig,
         window: &Window,
     ) {
-        use std::path::PathBuf;
         use std::fs;
 
-        let path = PathBuf::from(&folder.path);
+        let path = config.folder_path(folder);
         
         if !path.exists() {
-            warn!("文件夹不存在: {} ({})", folder.name, folder.path);
+            warn!("文件夹不存在: {} ({})", folder.name, path.display());
             return;
         }
 
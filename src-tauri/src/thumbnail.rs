@@ -0,0 +1,278 @@
+use anyhow::{Context, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use image::imageops::FilterType;
+use image::ImageFormat;
+use serde::Serialize;
+use std::collections::hash_map::DefaultHasher;
+use std::fs::{self, File};
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::{debug, info, warn};
+
+/// 缩略图缓存的默认容量上限（64 MB）
+pub const DEFAULT_CACHE_BYTES: u64 = 64 * 1024 * 1024;
+
+/// 缩略图边长范围（像素）
+const MIN_SIZE: u32 = 16;
+const MAX_SIZE: u32 = 1024;
+
+/// 缩略图结果
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Thumbnail {
+    /// 图片缩略图：src 为 data URL 或缓存文件路径
+    Image { src: String, width: u32, height: u32 },
+    /// 非图片文件：按扩展名生成的通用图标（SVG data URL）
+    Icon { src: String, category: String },
+}
+
+/// 磁盘缩略图缓存
+///
+/// 缓存文件名由源文件路径、修改时间和尺寸计算，源文件修改后自动失效。
+/// 命中时更新缓存文件的修改时间，超出容量时按修改时间从旧到新淘汰（LRU）。
+#[derive(Debug)]
+pub struct ThumbnailCache {
+    dir: PathBuf,
+    max_bytes: u64,
+    lock: Mutex<()>,
+}
+
+impl ThumbnailCache {
+    pub fn new<P: AsRef<Path>>(dir: P, max_bytes: u64) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+            max_bytes,
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// 获取文件的缩略图
+    ///
+    /// `inline` 为 true 时返回 data URL，否则返回缓存文件路径。
+    /// 非图片或无法解码的图片返回通用图标。
+    pub fn get(&self, path: &Path, size: u32, inline: bool) -> Result<Thumbnail> {
+        let metadata = fs::metadata(path).with_context(|| format!("无法读取文件信息: {:?}", path))?;
+        let size = size.clamp(MIN_SIZE, MAX_SIZE);
+
+        if metadata.is_dir() {
+            return Ok(icon_for("", size, true));
+        }
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("")
+            .to_lowercase();
+        if ImageFormat::from_extension(&extension).is_none() {
+            return Ok(icon_for(&extension, size, false));
+        }
+
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_nanos())
+            .unwrap_or(0);
+        let cache_path = self.dir.join(format!("{}.png", cache_key(path, modified, size)));
+
+        let (width, height) = match self.lookup(&cache_path) {
+            Some(dimensions) => dimensions,
+            None => match self.generate(path, &cache_path, size) {
+                Ok(dimensions) => dimensions,
+                Err(e) => {
+                    warn!("生成缩略图失败，使用通用图标: {:?}: {:#}", path, e);
+                    return Ok(icon_for(&extension, size, false));
+                }
+            },
+        };
+
+        let src = if inline {
+            let bytes = fs::read(&cache_path).with_context(|| format!("无法读取缩略图: {:?}", cache_path))?;
+            format!("data:image/png;base64,{}", STANDARD.encode(bytes))
+        } else {
+            cache_path.to_string_lossy().to_string()
+        };
+        Ok(Thumbnail::Image { src, width, height })
+    }
+
+    /// 清空缓存
+    pub fn clear(&self) -> Result<()> {
+        let _guard = self.lock.lock();
+        if self.dir.exists() {
+            fs::remove_dir_all(&self.dir).with_context(|| format!("无法删除缩略图缓存: {:?}", self.dir))?;
+        }
+        info!("缩略图缓存已清空");
+        Ok(())
+    }
+
+    /// 查找缓存并更新访问时间
+    fn lookup(&self, cache_path: &Path) -> Option<(u32, u32)> {
+        let dimensions = image::image_dimensions(cache_path).ok()?;
+        if let Ok(file) = File::options().append(true).open(cache_path) {
+            let _ = file.set_modified(SystemTime::now());
+        }
+        Some(dimensions)
+    }
+
+    /// 解码原图并写入缓存
+    fn generate(&self, source: &Path, cache_path: &Path, size: u32) -> Result<(u32, u32)> {
+        let image = image::io::Reader::open(source)?
+            .with_guessed_format()?
+            .decode()
+            .with_context(|| format!("无法解码图片: {:?}", source))?;
+
+        // 只缩小不放大
+        let thumbnail = if image.width() > size || image.height() > size {
+            image.resize(size, size, FilterType::Triangle)
+        } else {
+            image
+        };
+
+        let _guard = self.lock.lock();
+        fs::create_dir_all(&self.dir).with_context(|| format!("无法创建缓存目录: {:?}", self.dir))?;
+        let temp = cache_path.with_extension("png.tmp");
+        thumbnail
+            .save_with_format(&temp, ImageFormat::Png)
+            .with_context(|| format!("无法写入缩略图: {:?}", temp))?;
+        fs::rename(&temp, cache_path)?;
+        debug!("已生成缩略图: {:?} -> {:?}", source, cache_path);
+
+        if let Err(e) = self.evict() {
+            warn!("清理缩略图缓存失败: {}", e);
+        }
+        Ok((thumbnail.width(), thumbnail.height()))
+    }
+
+    /// 超出容量时删除最久未访问的缓存文件
+    fn evict(&self) -> Result<()> {
+        let mut entries = Vec::new();
+        let mut total = 0u64;
+        for entry in fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("png") {
+                continue;
+            }
+            let metadata = entry.metadata()?;
+            total += metadata.len();
+            entries.push((metadata.modified().unwrap_or(UNIX_EPOCH), metadata.len(), path));
+        }
+        if total <= self.max_bytes {
+            return Ok(());
+        }
+
+        entries.sort_by_key(|(modified, _, _)| *modified);
+        let mut removed = 0;
+        for (_, len, path) in entries {
+            if total <= self.max_bytes {
+                break;
+            }
+            if fs::remove_file(&path).is_ok() {
+                total -= len;
+                removed += 1;
+            }
+        }
+        info!("缩略图缓存超出容量，已淘汰 {} 个文件", removed);
+        Ok(())
+    }
+}
+
+/// 根据路径、修改时间和尺寸计算缓存键
+fn cache_key(path: &Path, modified: u128, size: u32) -> String {
+    let mut hasher = DefaultHasher::new();
+    path.hash(&mut hasher);
+    modified.hash(&mut hasher);
+    size.hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+/// 扩展名对应的文件类别和图标颜色
+fn category_of(extension: &str) -> (&'static str, &'static str) {
+    match extension {
+        "pdf" => ("pdf", "#ef4444"),
+        "doc" | "docx" | "odt" | "rtf" | "txt" | "md" => ("document", "#3b82f6"),
+        "xls" | "xlsx" | "ods" | "csv" => ("spreadsheet", "#22c55e"),
+        "ppt" | "pptx" | "odp" | "key" => ("presentation", "#f97316"),
+        "zip" | "rar" | "7z" | "tar" | "gz" | "xz" | "bz2" => ("archive", "#a16207"),
+        "mp3" | "wav" | "flac" | "aac" | "ogg" | "m4a" => ("audio", "#ec4899"),
+        "mp4" | "mkv" | "avi" | "mov" | "wmv" | "webm" => ("video", "#8b5cf6"),
+        "rs" | "js" | "ts" | "py" | "java" | "c" | "cpp" | "h" | "go" | "html" | "css" | "json" | "xml" => {
+            ("code", "#0ea5e9")
+        }
+        "exe" | "msi" | "dmg" | "deb" | "rpm" | "apk" => ("executable", "#64748b"),
+        "jpg" | "jpeg" | "png" | "gif" | "bmp" | "webp" | "tif" | "tiff" | "ico" | "svg" | "heic" => ("image", "#14b8a6"),
+        _ => ("file", "#667eea"),
+    }
+}
+
+/// 生成通用文件图标（带扩展名标签的 SVG）
+fn icon_for(extension: &str, size: u32, is_dir: bool) -> Thumbnail {
+    let (category, svg) = if is_dir {
+        let svg = format!(
+            r##"<svg xmlns="http://www.w3.org/2000/svg" width="{s}" height="{s}" viewBox="0 0 48 48"><path d="M4 10h15l4 4h21v26H4z" fill="#f59e0b"/></svg>"##,
+            s = size
+        );
+        ("folder", svg)
+    } else {
+        let (category, color) = category_of(extension);
+        // 标签只保留字母和数字，无需转义
+        let label: String = extension
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric())
+            .take(4)
+            .collect::<String>()
+            .to_uppercase();
+        let svg = format!(
+            r##"<svg xmlns="http://www.w3.org/2000/svg" width="{s}" height="{s}" viewBox="0 0 48 48"><path d="M10 4h20l10 10v30H10z" fill="{c}"/><path d="M30 4v10h10z" fill="#fff" fill-opacity=".4"/><text x="25" y="36" font-family="sans-serif" font-size="9" font-weight="bold" fill="#fff" text-anchor="middle">{l}</text></svg>"##,
+            s = size,
+            c = color,
+            l = label
+        );
+        (category, svg)
+    };
+
+    Thumbnail::Icon {
+        src: format!("data:image/svg+xml;base64,{}", STANDARD.encode(svg)),
+        category: category.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbImage;
+
+    #[test]
+    fn test_thumbnail_cache_and_fallback() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let photo = dir.join("photo.png");
+        RgbImage::new(200, 100).save(&photo).unwrap();
+        let notes = dir.join("notes.pdf");
+        fs::write(&notes, "%PDF").unwrap();
+
+        let cache = ThumbnailCache::new(dir.join("cache"), DEFAULT_CACHE_BYTES);
+        match cache.get(&photo, 64, false).unwrap() {
+            Thumbnail::Image { src, width, height } => {
+                assert_eq!((width, height), (64, 32));
+                assert!(Path::new(&src).exists());
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        match cache.get(&photo, 64, true).unwrap() {
+            Thumbnail::Image { src, .. } => assert!(src.starts_with("data:image/png;base64,")),
+            other => panic!("unexpected {:?}", other),
+        }
+        match cache.get(&notes, 64, true).unwrap() {
+            Thumbnail::Icon { category, .. } => assert_eq!(category, "pdf"),
+            other => panic!("unexpected {:?}", other),
+        }
+
+        // 容量为 0 时生成后立即淘汰全部缓存
+        let tiny = ThumbnailCache::new(dir.join("cache"), 0);
+        tiny.get(&photo, 32, false).unwrap();
+        assert_eq!(fs::read_dir(dir.join("cache")).unwrap().count(), 0);
+    }
+}
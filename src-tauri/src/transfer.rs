@@ -0,0 +1,630 @@
+use crate::journal::{self, Phase, TransferKind};
+use anyhow::{Context, Result};
+use chrono::Local;
+use filetime::FileTime;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// 复制和校验时每次读取的字节数
+const CHUNK_SIZE: usize = 256 * 1024;
+
+/// 两次进度事件之间的最短间隔
+const EMIT_INTERVAL: Duration = Duration::from_millis(200);
+
+/// 同一毫秒内生成多个 ID 时使用的序号
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// 复制和跨分区移动时保留的元数据
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct PreserveMetadata {
+    /// 修改时间和访问时间（基于日期的规则依赖修改时间）
+    pub timestamps: bool,
+    /// 权限位（Windows 上为只读属性）
+    pub permissions: bool,
+    /// 扩展属性（仅 Unix，包括 macOS 的标签和来源信息）
+    pub xattrs: bool,
+}
+
+impl Default for PreserveMetadata {
+    fn default() -> Self {
+        Self {
+            timestamps: true,
+            permissions: true,
+            xattrs: true,
+        }
+    }
+}
+
+/// 进度事件（前端事件名为 organize-progress）
+#[derive(Debug, Clone, Serialize)]
+pub struct ProgressEvent {
+    pub operation_id: String,
+    pub source: String,
+    pub current_file: String,
+    pub bytes_done: u64,
+    pub bytes_total: u64,
+    /// 操作已结束（完成、失败或取消）
+    pub finished: bool,
+    pub cancelled: bool,
+}
+
+/// 发送进度事件的回调
+pub type ProgressEmitter = Arc<dyn Fn(&ProgressEvent) + Send + Sync>;
+
+/// 正在进行的复制操作（用于发送进度和取消）
+#[derive(Default)]
+pub struct Operations {
+    active: Mutex<HashMap<String, Arc<AtomicBool>>>,
+    emitter: Mutex<Option<ProgressEmitter>>,
+}
+
+impl fmt::Debug for Operations {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let active = self.active.lock().map(|a| a.len()).unwrap_or(0);
+        f.debug_struct("Operations").field("active", &active).finish()
+    }
+}
+
+impl Operations {
+    /// 设置进度事件的发送方式
+    pub fn set_emitter(&self, emitter: ProgressEmitter) {
+        if let Ok(mut guard) = self.emitter.lock() {
+            *guard = Some(emitter);
+        }
+    }
+
+    /// 开始一个复制操作（会计算源文件或文件夹的总大小）
+    pub fn start(&self, source: &Path) -> Progress<'_> {
+        let id = format!("op-{}-{}", Local::now().format("%Y%m%d%H%M%S%3f"), NEXT_ID.fetch_add(1, Ordering::Relaxed));
+        let cancelled = Arc::new(AtomicBool::new(false));
+        if let Ok(mut active) = self.active.lock() {
+            active.insert(id.clone(), cancelled.clone());
+        }
+        let emitter = self.emitter.lock().ok().and_then(|e| e.clone());
+
+        let progress = Progress {
+            id,
+            source: source.to_string_lossy().to_string(),
+            total: total_size(source),
+            done: AtomicU64::new(0),
+            current: Mutex::new(String::new()),
+            cancelled,
+            emitter,
+            last_emit: Mutex::new(None),
+            registry: Some(self),
+        };
+        progress.emit(false);
+        progress
+    }
+
+    /// 请求取消操作，返回操作是否存在
+    pub fn cancel(&self, id: &str) -> bool {
+        match self.active.lock().ok().and_then(|active| active.get(id).cloned()) {
+            Some(flag) => {
+                flag.store(true, Ordering::SeqCst);
+                info!("已请求取消操作: {}", id);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 正在进行的操作 ID
+    pub fn active_ids(&self) -> Vec<String> {
+        self.active
+            .lock()
+            .map(|active| active.keys().cloned().collect())
+            .unwrap_or_default()
+    }
+}
+
+/// 单个复制操作的进度（结束时自动注销并发送结束事件）
+pub struct Progress<'a> {
+    id: String,
+    source: String,
+    total: u64,
+    done: AtomicU64,
+    current: Mutex<String>,
+    cancelled: Arc<AtomicBool>,
+    emitter: Option<ProgressEmitter>,
+    last_emit: Mutex<Option<Instant>>,
+    registry: Option<&'a Operations>,
+}
+
+impl Progress<'static> {
+    /// 不发送进度、不可取消的操作
+    pub fn detached() -> Self {
+        Progress {
+            id: String::new(),
+            source: String::new(),
+            total: 0,
+            done: AtomicU64::new(0),
+            current: Mutex::new(String::new()),
+            cancelled: Arc::new(AtomicBool::new(false)),
+            emitter: None,
+            last_emit: Mutex::new(None),
+            registry: None,
+        }
+    }
+}
+
+impl Progress<'_> {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// 已取消时返回错误
+    pub fn check(&self) -> Result<()> {
+        if self.is_cancelled() {
+            anyhow::bail!("操作已取消: {}", self.source);
+        }
+        Ok(())
+    }
+
+    /// 开始处理一个文件
+    fn begin_file(&self, path: &Path) {
+        if let Ok(mut current) = self.current.lock() {
+            *current = path.to_string_lossy().to_string();
+        }
+    }
+
+    /// 记录已复制的字节数（按间隔发送进度），已取消时返回错误
+    fn advance(&self, bytes: u64) -> Result<()> {
+        self.done.fetch_add(bytes, Ordering::Relaxed);
+        self.check()?;
+
+        let due = match self.last_emit.lock() {
+            Ok(mut last) => {
+                let due = last.is_none_or(|t| t.elapsed() >= EMIT_INTERVAL);
+                if due {
+                    *last = Some(Instant::now());
+                }
+                due
+            }
+            Err(_) => false,
+        };
+        if due {
+            self.emit(false);
+        }
+        Ok(())
+    }
+
+    fn emit(&self, finished: bool) {
+        let emitter = match &self.emitter {
+            Some(e) => e,
+            None => return,
+        };
+        emitter(&ProgressEvent {
+            operation_id: self.id.clone(),
+            source: self.source.clone(),
+            current_file: self.current.lock().map(|c| c.clone()).unwrap_or_default(),
+            bytes_done: self.done.load(Ordering::Relaxed),
+            bytes_total: self.total,
+            finished,
+            cancelled: self.is_cancelled(),
+        });
+    }
+}
+
+impl Drop for Progress<'_> {
+    fn drop(&mut self) {
+        if let Some(registry) = self.registry {
+            if let Ok(mut active) = registry.active.lock() {
+                active.remove(&self.id);
+            }
+            self.emit(true);
+        }
+    }
+}
+
+/// 复制文件并校验（SHA-256），返回校验值
+///
+/// 先写入目标目录中的暂存文件，复制时计算源文件的校验值，
+/// 写完后重新读取暂存文件校验，一致后才改为目标文件名。
+/// 任何一步失败或被取消都会删除暂存文件，不会留下不完整的目标文件。
+pub fn copy_file_verified(source: &Path, dest: &Path, preserve: &PreserveMetadata, progress: &Progress) -> Result<String> {
+    staged(TransferKind::Copy, source, dest, preserve, |staging| stage_file(source, staging, preserve, progress))
+}
+
+/// 递归复制文件夹并逐个校验文件，返回整个文件夹的校验值
+///
+/// 文件夹的校验值由所有文件的相对路径和校验值计算。
+/// 整个文件夹先复制到暂存文件夹，失败或被取消时删除暂存文件夹，目标中原有的内容保持不变。
+pub fn copy_dir_verified(source: &Path, dest: &Path, preserve: &PreserveMetadata, progress: &Progress) -> Result<String> {
+    staged(TransferKind::Copy, source, dest, preserve, |staging| stage_dir(source, staging, preserve, progress))
+}
+
+/// 跨分区移动：校验复制成功后才删除源文件，返回校验值
+pub fn move_verified(source: &Path, dest: &Path, preserve: &PreserveMetadata, progress: &Progress) -> Result<String> {
+    let checksum = staged(TransferKind::Move, source, dest, preserve, |staging| {
+        if source.is_dir() {
+            stage_dir(source, staging, preserve, progress)
+        } else {
+            stage_file(source, staging, preserve, progress)
+        }
+    })?;
+
+    info!("跨分区移动已校验 (SHA-256 {}): {:?} -> {:?}", checksum, source, dest);
+    Ok(checksum)
+}
+
+/// 在暂存路径中完成复制后再放到目标位置，移动时最后删除源文件
+///
+/// 每一步都记录在意图日志中，程序中途退出时由下次启动的恢复流程回滚或完成。
+fn staged<F>(kind: TransferKind, source: &Path, dest: &Path, preserve: &PreserveMetadata, stage: F) -> Result<String>
+where
+    F: FnOnce(&Path) -> Result<String>,
+{
+    let staging = temp_path(dest)?;
+    // 清理之前未记录的残留
+    remove_path(&staging).with_context(|| format!("无法清理暂存路径: {:?}", staging))?;
+
+    let journal = journal::global();
+    let id = journal.begin(kind, source, dest, &staging)?;
+    let checksum = match stage(&staging) {
+        Ok(checksum) => checksum,
+        Err(e) => {
+            if let Err(err) = remove_path(&staging) {
+                warn!("清理未完成的复制失败: {:?}: {}", staging, err);
+            }
+            journal.finish(&id);
+            return Err(e);
+        }
+    };
+
+    // 从这里开始中断时，恢复流程会完成操作而不是回滚
+    if let Err(e) = journal.set_phase(&id, Phase::Committing) {
+        // 中断后会按暂存阶段回滚，最多留下重复的源文件
+        warn!("更新操作记录失败: {}", e);
+    }
+    let merged = dest.is_dir() && staging.is_dir();
+    // 放置失败时保留记录，下次启动时继续完成
+    commit_staged(&staging, dest).with_context(|| format!("无法写入目标位置: {:?}", dest))?;
+    if merged {
+        // 合并到已有文件夹会改变其修改时间
+        preserve_metadata(source, dest, preserve);
+    }
+    if preserve.permissions && source.is_dir() {
+        apply_dir_permissions(source, dest);
+    }
+
+    let removed = match kind {
+        TransferKind::Move => remove_path(source).with_context(|| format!("已复制到目标位置，但删除源文件失败: {:?}", source)),
+        TransferKind::Copy => Ok(()),
+    };
+    journal.finish(&id);
+    removed?;
+    Ok(checksum)
+}
+
+/// 将暂存内容放到目标位置
+///
+/// 目标不存在时直接重命名；目标是文件夹时逐项合并；其他情况覆盖目标。
+pub(crate) fn commit_staged(staging: &Path, dest: &Path) -> Result<()> {
+    let staging_is_dir = fs::symlink_metadata(staging)
+        .with_context(|| format!("暂存内容不存在: {:?}", staging))?
+        .is_dir();
+
+    match fs::symlink_metadata(dest) {
+        Ok(metadata) if metadata.is_dir() && staging_is_dir => {
+            for entry in fs::read_dir(staging)? {
+                let entry = entry?;
+                commit_staged(&entry.path(), &dest.join(entry.file_name()))?;
+            }
+            fs::remove_dir(staging)?;
+        }
+        Ok(metadata) => {
+            // 文件和文件夹互相覆盖时先删除目标
+            if metadata.is_dir() || staging_is_dir {
+                remove_path(dest)?;
+            }
+            fs::rename(staging, dest)?;
+        }
+        Err(_) => fs::rename(staging, dest)?,
+    }
+    Ok(())
+}
+
+/// 删除文件或文件夹（不跟随符号链接），不存在时忽略
+pub(crate) fn remove_path(path: &Path) -> std::io::Result<()> {
+    match fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.is_dir() => fs::remove_dir_all(path),
+        Ok(_) => fs::remove_file(path),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e),
+    }
+}
+
+/// 复制文件到暂存路径并校验，返回校验值
+fn stage_file(source: &Path, staging: &Path, preserve: &PreserveMetadata, progress: &Progress) -> Result<String> {
+    progress.begin_file(source);
+    let source_hash = copy_and_hash(source, staging, progress)?;
+    let dest_hash = hash_reader(staging, progress)?;
+    if source_hash != dest_hash {
+        anyhow::bail!("校验失败，复制的文件与源文件不一致: {:?}", source);
+    }
+    // 校验时读取暂存文件会改变访问时间，之后再写入元数据
+    preserve_metadata(source, staging, preserve);
+    Ok(source_hash)
+}
+
+/// 复制文件夹到暂存路径并逐个校验，返回整个文件夹的校验值
+fn stage_dir(source: &Path, staging: &Path, preserve: &PreserveMetadata, progress: &Progress) -> Result<String> {
+    let mut hashes = Vec::new();
+    copy_dir_inner(source, staging, Path::new(""), preserve, progress, &mut hashes)?;
+
+    hashes.sort();
+    let mut hasher = Sha256::new();
+    for (relative, hash) in &hashes {
+        hasher.update(relative.as_bytes());
+        hasher.update([0]);
+        hasher.update(hash.as_bytes());
+        hasher.update([b'\n']);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// 计算文件的 SHA-256 校验值（流式读取）
+pub fn hash_file(path: &Path) -> Result<String> {
+    hash_reader(path, &Progress::detached())
+}
+
+fn hash_reader(path: &Path, progress: &Progress) -> Result<String> {
+    let mut file = File::open(path).with_context(|| format!("无法读取文件: {:?}", path))?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; CHUNK_SIZE];
+    loop {
+        progress.check()?;
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// 分块复制文件内容并同时计算源文件的校验值
+fn copy_and_hash(source: &Path, dest: &Path, progress: &Progress) -> Result<String> {
+    let mut reader = File::open(source).with_context(|| format!("无法读取文件: {:?}", source))?;
+    let mut writer = File::create(dest).with_context(|| format!("无法创建文件: {:?}", dest))?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; CHUNK_SIZE];
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+        writer.write_all(&buf[..n])?;
+        progress.advance(n as u64)?;
+    }
+    writer.sync_all()?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+fn copy_dir_inner(
+    source: &Path,
+    dest: &Path,
+    relative: &Path,
+    preserve: &PreserveMetadata,
+    progress: &Progress,
+    hashes: &mut Vec<(String, String)>,
+) -> Result<()> {
+    fs::create_dir_all(dest).with_context(|| format!("创建目标目录失败: {:?}", dest))?;
+
+    for entry in fs::read_dir(source).with_context(|| format!("读取源目录失败: {:?}", source))? {
+        let entry = entry?;
+        let src_path = entry.path();
+        let dst_path = dest.join(entry.file_name());
+        let rel_path = relative.join(entry.file_name());
+
+        if entry.file_type()?.is_dir() {
+            copy_dir_inner(&src_path, &dst_path, &rel_path, preserve, progress, hashes)?;
+        } else {
+            let hash = stage_file(&src_path, &dst_path, preserve, progress)
+                .with_context(|| format!("复制文件失败: {:?} -> {:?}", src_path, dst_path))?;
+            hashes.push((rel_path.to_string_lossy().replace('\\', "/"), hash));
+        }
+    }
+
+    // 写入内容会改变文件夹的修改时间，所以在复制完子项后再写入；
+    // 权限在放到目标位置后才写入，见 apply_dir_permissions
+    let without_permissions = PreserveMetadata { permissions: false, ..*preserve };
+    preserve_metadata(source, dest, &without_permissions);
+    Ok(())
+}
+
+/// 将源文件夹树中各文件夹的权限写入目标（放到目标位置之后执行）
+///
+/// 暂存文件夹在此之前保持可写，因此只读的源文件夹（例如来自挂载的介质）
+/// 也能合并到已有文件夹，失败时暂存文件夹也能被清理。
+fn apply_dir_permissions(source: &Path, dest: &Path) {
+    if let Ok(entries) = fs::read_dir(source) {
+        for entry in entries.flatten() {
+            if entry.file_type().is_ok_and(|t| t.is_dir()) {
+                apply_dir_permissions(&entry.path(), &dest.join(entry.file_name()));
+            }
+        }
+    }
+
+    let result = fs::metadata(source).and_then(|metadata| fs::set_permissions(dest, metadata.permissions()));
+    if let Err(e) = result {
+        warn!("保留权限失败: {:?}: {}", dest, e);
+    }
+}
+
+/// 将源文件或文件夹的元数据写入复制结果，失败时只记录警告
+///
+/// 先写扩展属性和时间，最后写权限，避免只读权限导致其他元数据无法写入。
+pub fn preserve_metadata(source: &Path, dest: &Path, preserve: &PreserveMetadata) {
+    let metadata = match fs::metadata(source) {
+        Ok(m) => m,
+        Err(e) => {
+            warn!("无法读取源文件元数据: {:?}: {}", source, e);
+            return;
+        }
+    };
+
+    if preserve.xattrs {
+        copy_xattrs(source, dest);
+    }
+    if preserve.timestamps {
+        let accessed = FileTime::from_last_access_time(&metadata);
+        let modified = FileTime::from_last_modification_time(&metadata);
+        if let Err(e) = filetime::set_file_times(dest, accessed, modified) {
+            warn!("保留修改时间失败: {:?}: {}", dest, e);
+        }
+    }
+    if preserve.permissions {
+        if let Err(e) = fs::set_permissions(dest, metadata.permissions()) {
+            warn!("保留权限失败: {:?}: {}", dest, e);
+        }
+    }
+}
+
+/// 复制扩展属性（逐个复制，无权限写入的属性会被跳过）
+#[cfg(unix)]
+fn copy_xattrs(source: &Path, dest: &Path) {
+    if !xattr::SUPPORTED_PLATFORM {
+        return;
+    }
+    let names = match xattr::list(source) {
+        Ok(names) => names,
+        Err(e) => {
+            warn!("无法读取扩展属性: {:?}: {}", source, e);
+            return;
+        }
+    };
+    for name in names {
+        let result = xattr::get(source, &name).and_then(|value| match value {
+            Some(value) => xattr::set(dest, &name, &value),
+            None => Ok(()),
+        });
+        if let Err(e) = result {
+            warn!("复制扩展属性 {:?} 失败: {:?}: {}", name, dest, e);
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn copy_xattrs(_source: &Path, _dest: &Path) {}
+
+/// 文件或文件夹的总大小（不跟随符号链接）
+pub(crate) fn total_size(path: &Path) -> u64 {
+    let metadata = match fs::symlink_metadata(path) {
+        Ok(m) => m,
+        Err(_) => return 0,
+    };
+    if !metadata.is_dir() {
+        return metadata.len();
+    }
+    fs::read_dir(path)
+        .map(|entries| entries.flatten().map(|e| total_size(&e.path())).sum())
+        .unwrap_or(0)
+}
+
+/// 目标旁的暂存路径
+fn temp_path(dest: &Path) -> Result<PathBuf> {
+    let name = dest
+        .file_name()
+        .and_then(|n| n.to_str())
+        .context("无法获取文件名")?;
+    Ok(dest.with_file_name(format!(".{}.floatsort-part", name)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verified_copy_and_move() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        fs::create_dir_all(dir.join("src/nested")).unwrap();
+        fs::write(dir.join("src/a.txt"), "abc").unwrap();
+        fs::write(dir.join("src/nested/b.txt"), "hello").unwrap();
+
+        // "abc" 的 SHA-256
+        let none = Progress::detached();
+        let keep = PreserveMetadata::default();
+        let checksum = copy_file_verified(&dir.join("src/a.txt"), &dir.join("a.txt"), &keep, &none).unwrap();
+        assert_eq!(checksum, "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+        assert_eq!(hash_file(&dir.join("a.txt")).unwrap(), checksum);
+
+        // 目标目录不存在时复制失败，不留下临时文件
+        assert!(copy_file_verified(&dir.join("src/a.txt"), &dir.join("missing/a.txt"), &keep, &none).is_err());
+        assert!(copy_file_verified(&dir.join("nope.txt"), &dir.join("b.txt"), &keep, &none).is_err());
+        assert!(!dir.join(".b.txt.floatsort-part").exists());
+
+        // 取消后清理已创建的目标文件夹，源文件夹保持不变
+        let operations = Operations::default();
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = events.clone();
+        operations.set_emitter(Arc::new(move |e: &ProgressEvent| sink.lock().unwrap().push(e.clone())));
+        {
+            let progress = operations.start(&dir.join("src"));
+            assert_eq!(progress.total, 8);
+            assert!(operations.cancel(progress.id()));
+            assert!(move_verified(&dir.join("src"), &dir.join("dst"), &keep, &progress).is_err());
+        }
+        assert!(!dir.join("dst").exists() && dir.join("src/nested/b.txt").exists());
+        assert!(operations.active_ids().is_empty());
+        assert!(events.lock().unwrap().last().is_some_and(|e| e.finished && e.cancelled));
+
+        // 文件和文件夹的修改时间都会被保留
+        let old = FileTime::from_unix_time(1_600_000_000, 0);
+        filetime::set_file_mtime(dir.join("src/nested/b.txt"), old).unwrap();
+        filetime::set_file_mtime(dir.join("src/nested"), old).unwrap();
+        let tree = move_verified(&dir.join("src"), &dir.join("dst"), &keep, &none).unwrap();
+        assert_eq!(tree.len(), 64);
+        assert!(!dir.join("src").exists());
+        assert_eq!(fs::read_to_string(dir.join("dst/nested/b.txt")).unwrap(), "hello");
+        for path in ["dst/nested/b.txt", "dst/nested"] {
+            let metadata = fs::metadata(dir.join(path)).unwrap();
+            assert_eq!(FileTime::from_last_modification_time(&metadata), old);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_read_only_folder_merged() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        fs::create_dir_all(dir.join("src/nested")).unwrap();
+        fs::create_dir_all(dir.join("dst/nested")).unwrap();
+        fs::write(dir.join("src/nested/b.txt"), "hello").unwrap();
+        for path in ["src/nested", "src"] {
+            fs::set_permissions(dir.join(path), fs::Permissions::from_mode(0o555)).unwrap();
+        }
+
+        let keep = PreserveMetadata::default();
+        copy_dir_verified(&dir.join("src"), &dir.join("dst"), &keep, &Progress::detached()).unwrap();
+        assert_eq!(fs::read_to_string(dir.join("dst/nested/b.txt")).unwrap(), "hello");
+        assert!(!dir.join(".dst.floatsort-part").exists());
+        for path in ["dst/nested", "dst"] {
+            assert_eq!(fs::metadata(dir.join(path)).unwrap().permissions().mode() & 0o777, 0o555);
+        }
+
+        // 恢复写权限，临时文件夹才能被删除
+        for path in ["src", "src/nested", "dst", "dst/nested"] {
+            fs::set_permissions(dir.join(path), fs::Permissions::from_mode(0o755)).unwrap();
+        }
+    }
+}
@@ -0,0 +1,276 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration as ChronoDuration, Local, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use tracing::{info, warn};
+
+/// 记录时间与回收站中删除时间的最大允许误差（秒）
+const TIME_TOLERANCE_SECS: i64 = 5;
+
+/// 同一毫秒内生成多个 ID 时使用的序号
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// FloatSort 移到回收站的一条记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrashRecord {
+    pub id: String,
+    pub original_path: String,
+    /// 调用系统回收站前后的时间（Unix 秒），用于在回收站中找到对应条目
+    pub started_at: i64,
+    pub finished_at: i64,
+}
+
+/// 回收站中仍然存在的记录
+#[derive(Debug, Clone, Serialize)]
+pub struct TrashedItem {
+    pub id: String,
+    pub original_path: String,
+    pub name: String,
+    pub trashed_at: DateTime<Utc>,
+}
+
+/// FloatSort 移到回收站的文件记录
+///
+/// 系统回收站本身不区分是谁删除的文件，这里记录原路径和删除时间，
+/// 列出、恢复和清空时再与系统回收站中的条目匹配。
+/// 只有 Windows 和遵循 FreeDesktop 规范的 Linux 支持列出回收站。
+/// 未指定记录文件时不记录（用于预览和测试）。
+#[derive(Debug, Default)]
+pub struct TrashLog {
+    path: Option<PathBuf>,
+    lock: Mutex<()>,
+}
+
+impl TrashLog {
+    pub fn open<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: Some(path.as_ref().to_path_buf()),
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// 将文件移到系统回收站并记录
+    pub fn trash(&self, source: &Path) -> Result<()> {
+        let started_at = Utc::now().timestamp();
+        trash::delete(source)
+            .with_context(|| format!("移动文件到回收站失败: {:?}", source))?;
+        let finished_at = Utc::now().timestamp();
+        info!("文件已移动到回收站: {:?}", source);
+
+        if self.path.is_none() {
+            return Ok(());
+        }
+        let record = TrashRecord {
+            id: format!("{}-{}", Local::now().format("%Y%m%d%H%M%S%3f"), NEXT_ID.fetch_add(1, Ordering::Relaxed)),
+            original_path: source.to_string_lossy().to_string(),
+            started_at,
+            finished_at,
+        };
+
+        // 文件已经在回收站中，记录失败只影响撤销
+        let _guard = self.lock.lock();
+        let result = self.load().and_then(|mut records| {
+            records.push(record);
+            self.save(&records)
+        });
+        if let Err(e) = result {
+            warn!("记录回收站条目失败: {}", e);
+        }
+        Ok(())
+    }
+
+    /// 列出仍在回收站中的记录（最新的在前），已不在回收站中的记录会被移除
+    pub fn list(&self) -> Result<Vec<TrashedItem>> {
+        let _guard = self.lock.lock();
+        let items = os_trash::list()?;
+        let records = self.load()?;
+
+        let mut kept = Vec::new();
+        let mut result = Vec::new();
+        for record in records {
+            if let Some(item) = find_item(&items, &record) {
+                result.push(TrashedItem {
+                    id: record.id.clone(),
+                    original_path: record.original_path.clone(),
+                    name: item.name.clone(),
+                    trashed_at: Utc.timestamp_opt(item.time_deleted.max(record.finished_at), 0).single().unwrap_or_else(Utc::now),
+                });
+                kept.push(record);
+            }
+        }
+        self.save(&kept)?;
+
+        result.sort_by_key(|item| std::cmp::Reverse(item.trashed_at));
+        Ok(result)
+    }
+
+    /// 将记录对应的文件恢复到原位置，原位置已有文件时失败
+    pub fn restore(&self, id: &str) -> Result<String> {
+        let _guard = self.lock.lock();
+        let mut records = self.load()?;
+        let index = records
+            .iter()
+            .position(|r| r.id == id)
+            .with_context(|| format!("回收站记录不存在: {}", id))?;
+        let record = &records[index];
+
+        let items = os_trash::list()?;
+        let item = find_item(&items, record)
+            .with_context(|| format!("文件已不在回收站中: {}", record.original_path))?
+            .clone();
+        if Path::new(&record.original_path).symlink_metadata().is_ok() {
+            anyhow::bail!("原位置已存在同名文件: {}", record.original_path);
+        }
+
+        os_trash::restore(item)?;
+        let restored = records.remove(index).original_path;
+        self.save(&records)?;
+
+        info!("已从回收站恢复: {}", restored);
+        Ok(restored)
+    }
+
+    /// 从回收站彻底删除早于指定天数的记录（None 表示全部），返回删除的数量
+    pub fn purge(&self, older_than_days: Option<u32>) -> Result<usize> {
+        let _guard = self.lock.lock();
+        let cutoff = older_than_days.map(|days| (Utc::now() - ChronoDuration::days(days as i64)).timestamp());
+        let items = os_trash::list()?;
+        let records = self.load()?;
+
+        let mut kept = Vec::new();
+        let mut to_purge = Vec::new();
+        for record in records {
+            match find_item(&items, &record) {
+                Some(item) if cutoff.is_none_or(|c| record.finished_at < c) => to_purge.push(item.clone()),
+                Some(_) => kept.push(record),
+                // 已被恢复或在系统中清空
+                None => {}
+            }
+        }
+
+        let count = to_purge.len();
+        if count > 0 {
+            os_trash::purge(to_purge)?;
+        }
+        self.save(&kept)?;
+
+        info!("已从回收站彻底删除 {} 个文件", count);
+        Ok(count)
+    }
+
+    fn load(&self) -> Result<Vec<TrashRecord>> {
+        let path = match &self.path {
+            Some(p) if p.exists() => p,
+            _ => return Ok(Vec::new()),
+        };
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("无法读取回收站记录: {:?}", path))?;
+        serde_json::from_str(&content).with_context(|| format!("无法解析回收站记录: {:?}", path))
+    }
+
+    fn save(&self, records: &[TrashRecord]) -> Result<()> {
+        let path = match &self.path {
+            Some(p) => p,
+            None => return Ok(()),
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(records)?)
+            .with_context(|| format!("无法写入回收站记录: {:?}", path))
+    }
+}
+
+/// 在回收站中查找记录对应的条目（原路径相同且删除时间最接近）
+fn find_item<'a>(items: &'a [trash::TrashItem], record: &TrashRecord) -> Option<&'a trash::TrashItem> {
+    let original = Path::new(&record.original_path);
+    items
+        .iter()
+        .filter(|item| item.original_path() == original)
+        .filter(|item| {
+            // 无法读取删除时间时（-1）只按路径匹配
+            item.time_deleted < 0
+                || (item.time_deleted >= record.started_at - TIME_TOLERANCE_SECS
+                    && item.time_deleted <= record.finished_at + TIME_TOLERANCE_SECS)
+        })
+        .min_by_key(|item| (item.time_deleted - record.finished_at).abs())
+}
+
+/// 系统回收站操作（仅 Windows 和 FreeDesktop 支持）
+#[cfg(any(
+    target_os = "windows",
+    all(unix, not(target_os = "macos"), not(target_os = "ios"), not(target_os = "android"))
+))]
+mod os_trash {
+    use anyhow::{Context, Result};
+    use trash::{os_limited, TrashItem};
+
+    pub fn list() -> Result<Vec<TrashItem>> {
+        os_limited::list().context("无法读取系统回收站")
+    }
+
+    pub fn restore(item: TrashItem) -> Result<()> {
+        os_limited::restore_all([item]).context("从回收站恢复失败")
+    }
+
+    pub fn purge(items: Vec<TrashItem>) -> Result<()> {
+        os_limited::purge_all(items).context("清空回收站条目失败")
+    }
+}
+
+#[cfg(not(any(
+    target_os = "windows",
+    all(unix, not(target_os = "macos"), not(target_os = "ios"), not(target_os = "android"))
+)))]
+mod os_trash {
+    use anyhow::Result;
+    use trash::TrashItem;
+
+    pub fn list() -> Result<Vec<TrashItem>> {
+        anyhow::bail!("当前系统不支持读取回收站")
+    }
+
+    pub fn restore(_item: TrashItem) -> Result<()> {
+        anyhow::bail!("当前系统不支持从回收站恢复")
+    }
+
+    pub fn purge(_items: Vec<TrashItem>) -> Result<()> {
+        anyhow::bail!("当前系统不支持清空回收站条目")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(parent: &str, name: &str, time_deleted: i64) -> trash::TrashItem {
+        trash::TrashItem {
+            id: format!("{}/{}-{}", parent, name, time_deleted).into(),
+            name: name.to_string(),
+            original_parent: PathBuf::from(parent),
+            time_deleted,
+        }
+    }
+
+    #[test]
+    fn test_find_item_by_path_and_time() {
+        let record = TrashRecord {
+            id: "1".to_string(),
+            original_path: "/home/u/Downloads/a.txt".to_string(),
+            started_at: 1000,
+            finished_at: 1001,
+        };
+        // 同一路径被删除过多次时选择时间最接近的条目
+        let items = vec![
+            item("/home/u/Downloads", "a.txt", 500),
+            item("/home/u/Downloads", "a.txt", 1002),
+            item("/home/u/Downloads", "b.txt", 1001),
+        ];
+        assert_eq!(find_item(&items, &record).unwrap().time_deleted, 1002);
+        assert!(find_item(&items[..1], &record).is_none());
+        assert!(find_item(&[item("/home/u/Downloads", "a.txt", -1)], &record).is_some());
+    }
+}
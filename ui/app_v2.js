@@ -343,6 +343,24 @@ function updateUILanguage() {
         conflictStrategySelect.options[0].textContent = t('rules.conflictSkip');
         conflictStrategySelect.options[1].textContent = t('rules.conflictReplace');
         conflictStrategySelect.options[2].textContent = t('rules.conflictRename');
+        conflictStrategySelect.options[3].textContent = t('rules.conflictKeepNewer');
+        conflictStrategySelect.options[4].textContent = t('rules.conflictKeepLarger');
+        conflictStrategySelect.options[5].textContent = t('rules.conflictSkipIfIdentical');
+        conflictStrategySelect.options[6].textContent = t('rules.conflictAppendTimestamp');
+        conflictStrategySelect.options[7].textContent = t('rules.conflictVersioned');
+        conflictStrategySelect.options[8].textContent = t('rules.conflictAsk');
+        conflictStrategySelect.options[9].textContent = t('rules.conflictMerge');
+    }
+    
+    const mergeFileStrategyLabel = document.getElementById('mergeFileStrategyLabel');
+    if (mergeFileStrategyLabel) mergeFileStrategyLabel.textContent = t('rules.mergeFileStrategy');
+    
+    // 合并时同名文件的策略与冲突策略的前 8 项相同
+    const mergeFileStrategySelect = document.getElementById('mergeFileStrategy');
+    if (mergeFileStrategySelect && conflictStrategySelect) {
+        for (let i = 0; i < mergeFileStrategySelect.options.length; i++) {
+            mergeFileStrategySelect.options[i].textContent = conflictStrategySelect.options[i].textContent;
+        }
     }
     
     // 8. 更新文件夹模态框
@@ -459,6 +477,23 @@ function getRuleLabel(index) {
     return String.fromCharCode(65 + index); // A, B, C, ...
 }
 
+// 获取规则的目标文件夹（动作流水线中第一个移动/复制步骤的目标）
+function getRuleDestination(rule) {
+    const actions = getRuleActions(rule);
+    const index = getRuleDestinationIndex(actions);
+    return index >= 0 ? actions[index].destination : undefined;
+}
+
+// 规则的动作流水线（兼容旧版单一 action 写法）
+function getRuleActions(rule) {
+    return rule.actions || (rule.action ? [rule.action] : []);
+}
+
+// 流水线中决定目标文件夹的步骤（第一个移动或复制步骤）的位置，没有时为 -1
+function getRuleDestinationIndex(actions) {
+    return actions.findIndex(a => a.type === 'MoveTo' || a.type === 'CopyTo');
+}
+
 // 更新视图图标
 // 切换视图下拉菜单
 window.toggleViewDropdown = function() {
@@ -884,6 +919,9 @@ function setupEventListeners() {
     // 规则目标文件夹选择按钮
     document.getElementById('browseTargetFolderBtn').addEventListener('click', selectTargetFolder);
     
+    // 冲突策略为合并时显示同名文件的策略
+    document.getElementById('conflictStrategy').addEventListener('change', updateMergeFileStrategyVisibility);
+    
     // 批量确认窗口
     document.getElementById('closeBatchModal').addEventListener('click', closeBatchModal);
     document.getElementById('cancelBatch').addEventListener('click', closeBatchModal);
@@ -1021,7 +1059,7 @@ function setupBackendListeners() {
         addActivity(`${t('activity.fileDetected')}: ${fileName}`);
         
         // 找到文件所属的文件夹
-        const folder = appState.folders.find(f => filePath.startsWith(f.path));
+        const folder = appState.folders.find(f => filePath.startsWith(f.resolved_path || f.path));
         
         if (!folder) {
             console.warn('[文件检测] 未找到对应的监控文件夹');
@@ -1033,11 +1071,14 @@ function setupBackendListeners() {
             // 自动处理模式：立即整理文件
             console.log('[文件检测] 自动处理模式，立即整理');
             try {
-                const result = await invoke('process_file', { path: filePath });
+                const result = await invoke('process_file', { path: filePath, folderId: folder.id });
                 if (!result) {
                     console.log('[文件检测] 文件未匹配任何规则');
                     addActivity(`${t('activity.fileNotMatched')}: ${fileName}`);
                 }
+                if (result === '文件等待处理冲突') {
+                    addActivity(`${t('activity.conflictPending')}: ${fileName}`, 'warning');
+                }
                 // 成功的整理信息由 file-organized 事件处理
                 appState.filesProcessed++;
                 updateStats();
@@ -1147,6 +1188,12 @@ function setupBackendListeners() {
     });
 }
 
+// 查找文件所在的监控文件夹 ID（不在任何监控文件夹中时为 null，使用全局规则）
+function findWatchFolderId(filePath) {
+    const folder = appState.folders.find(f => filePath.startsWith(f.resolved_path || f.path));
+    return folder ? folder.id : null;
+}
+
 // 处理拖拽的文件
 async function processDraggedFiles(files) {
     for (const filePath of files) {
@@ -1154,7 +1201,7 @@ async function processDraggedFiles(files) {
         
         try {
             addActivity(`${t('activity.fileProcessing')}: ${fileName}`);
-            const result = await invoke('process_file', { path: filePath });
+            const result = await invoke('process_file', { path: filePath, folderId: findWatchFolderId(filePath) });
             
             if (result) {
                 addActivity(
@@ -1683,7 +1730,10 @@ async function processFilesWithRuleDirectly(files, ruleId) {
                 ruleId: ruleId
             });
             
-            if (result && result !== '文件未匹配任何规则') {
+            if (result === '文件等待处理冲突') {
+                addActivity(`⏸ ${file.name} ${t('activity.conflictPending')} [${rule.name}]`, 'warning');
+                skipCount++;
+            } else if (result && result !== '文件未匹配任何规则') {
                 addActivity(
                     `✅ <strong>${file.name}</strong>`,
                     'success',
@@ -2157,7 +2207,7 @@ function renderRules() {
         const folderNames = usedByFolders.map(f => f.name).join('、') || '暂未被任何文件夹使用';
         
         // 处理目标路径显示
-        const destination = rule.action.destination;
+        const destination = getRuleDestination(rule);
         const isRecycleBin = destination === '{recycle}'; // 检测回收站
         const isAbsolutePath = /^[A-Z]:\\/i.test(destination); // 检测绝对路径（Windows）
         let displayPath = destination;
@@ -2257,7 +2307,7 @@ function renderSplitView() {
     // 按目标文件夹分组规则
     const groups = new Map();
     appState.rules.forEach(rule => {
-        const dest = rule.action.destination || '(未设置)';
+        const dest = getRuleDestination(rule) || '(未设置)';
         if (!groups.has(dest)) {
             groups.set(dest, []);
         }
@@ -2571,7 +2621,7 @@ function updateSplitFolderSelectionState() {
         if (!destination) return;
         
         // 获取该组的所有规则
-        const groupRules = appState.rules.filter(r => (r.action.destination || '(未设置)') === destination);
+        const groupRules = appState.rules.filter(r => (getRuleDestination(r) || '(未设置)') === destination);
         const groupRuleIds = groupRules.map(r => r.id);
         
         // 检查是否所有规则都被选中
@@ -2600,7 +2650,7 @@ window.selectFolderInSplit = function(destination, event) {
     appState.selectedFolderId = destination;
     
     // 获取该组的所有规则ID
-    const groupRules = appState.rules.filter(r => (r.action.destination || '(未设置)') === destination);
+    const groupRules = appState.rules.filter(r => (getRuleDestination(r) || '(未设置)') === destination);
     const groupRuleIds = groupRules.map(r => r.id);
     
     if (event && event.ctrlKey) {
@@ -2664,7 +2714,7 @@ window.applyRulesToFolder = function(destination, event) {
     }
     
     // 获取该目标文件夹的所有规则
-    const rules = appState.rules.filter(r => (r.action.destination || '(未设置)') === destination);
+    const rules = appState.rules.filter(r => (getRuleDestination(r) || '(未设置)') === destination);
     
     if (rules.length === 0) {
         showNotification('该文件夹没有规则', 'warning');
@@ -2732,7 +2782,7 @@ window.confirmApplyRules = async function(destination) {
     }
     
     const selectedFolderIds = Array.from(checkboxes).map(cb => cb.value);
-    const rules = appState.rules.filter(r => (r.action.destination || '(未设置)') === destination);
+    const rules = appState.rules.filter(r => (getRuleDestination(r) || '(未设置)') === destination);
     const ruleIds = rules.map(r => r.id);
     
     try {
@@ -2770,7 +2820,7 @@ window.moveRuleSplitUp = async function(index) {
     // 按目标文件夹分组
     const groups = new Map();
     appState.rules.forEach(rule => {
-        const dest = rule.action.destination || '(未设置)';
+        const dest = getRuleDestination(rule) || '(未设置)';
         if (!groups.has(dest)) {
             groups.set(dest, []);
         }
@@ -2798,7 +2848,7 @@ window.moveRuleSplitDown = async function(index) {
     // 按目标文件夹分组
     const groups = new Map();
     appState.rules.forEach(rule => {
-        const dest = rule.action.destination || '(未设置)';
+        const dest = getRuleDestination(rule) || '(未设置)';
         if (!groups.has(dest)) {
             groups.set(dest, []);
         }
@@ -2981,7 +3031,7 @@ async function openFolderModal(folderId = null) {
         }
         
         rulesCheckboxes.innerHTML = orderedRules.map((rule, index) => {
-            const destPath = rule.action?.destination || '(未设置)';
+            const destPath = getRuleDestination(rule) || '(未设置)';
             return `
             <div class="rule-sort-item" data-rule-id="${rule.id}" data-index="${index}">
                 <label class="checkbox-label">
@@ -3928,6 +3978,24 @@ async function openRuleModal(ruleId = null) {
         conflictStrategySelect.options[0].textContent = t('rules.conflictSkip');
         conflictStrategySelect.options[1].textContent = t('rules.conflictReplace');
         conflictStrategySelect.options[2].textContent = t('rules.conflictRename');
+        conflictStrategySelect.options[3].textContent = t('rules.conflictKeepNewer');
+        conflictStrategySelect.options[4].textContent = t('rules.conflictKeepLarger');
+        conflictStrategySelect.options[5].textContent = t('rules.conflictSkipIfIdentical');
+        conflictStrategySelect.options[6].textContent = t('rules.conflictAppendTimestamp');
+        conflictStrategySelect.options[7].textContent = t('rules.conflictVersioned');
+        conflictStrategySelect.options[8].textContent = t('rules.conflictAsk');
+        conflictStrategySelect.options[9].textContent = t('rules.conflictMerge');
+    }
+    
+    const mergeFileStrategyLabel = document.getElementById('mergeFileStrategyLabel');
+    if (mergeFileStrategyLabel) mergeFileStrategyLabel.textContent = t('rules.mergeFileStrategy');
+    
+    // 合并时同名文件的策略与冲突策略的前 8 项相同
+    const mergeFileStrategySelect = document.getElementById('mergeFileStrategy');
+    if (mergeFileStrategySelect && conflictStrategySelect) {
+        for (let i = 0; i < mergeFileStrategySelect.options.length; i++) {
+            mergeFileStrategySelect.options[i].textContent = conflictStrategySelect.options[i].textContent;
+        }
     }
     
     // 更新条件类型选项
@@ -3949,11 +4017,12 @@ async function openRuleModal(ruleId = null) {
         const rule = appState.rules.find(r => r.id === ruleId);
         if (!rule) return;
         document.getElementById('ruleName').value = rule.name;
-        document.getElementById('targetFolder').value = rule.action.destination;
+        document.getElementById('targetFolder').value = getRuleDestination(rule) || '';
         
         // 设置文件冲突处理策略
         const conflictStrategy = rule.conflict_strategy || 'skip';
         document.getElementById('conflictStrategy').value = conflictStrategy;
+        document.getElementById('mergeFileStrategy').value = rule.merge_file_strategy || 'skip';
         
         // 检查是否有 FileType 条件，并设置复选框
         const fileTypeCondition = rule.conditions.find(c => c.type === 'FileType');
@@ -4027,6 +4096,8 @@ async function openRuleModal(ruleId = null) {
         }
     }
     
+    updateMergeFileStrategyVisibility();
+    
     // 初始化条件构建器和扩展名标签
     updateConditionInputs();
     renderConditions();
@@ -4102,16 +4173,31 @@ async function saveRule() {
     
     // 获取文件冲突处理策略
     const conflictStrategy = document.getElementById('conflictStrategy').value || 'skip';
+    const mergeFileStrategy = document.getElementById('mergeFileStrategy').value || 'skip';
+    
+    // 编辑时保留流水线中的所有步骤，只替换目标文件夹所在步骤的目标路径；
+    // 原规则没有移动或复制步骤时，在末尾添加移动步骤
+    const editingRule = appState.rules.find(r => r.id === appState.editingRuleId);
+    const actions = editingRule ? getRuleActions(editingRule).map(a => ({ ...a })) : [];
+    const destinationIndex = getRuleDestinationIndex(actions);
+    if (destinationIndex >= 0) {
+        actions[destinationIndex].destination = target;
+    } else {
+        actions.push({ type: 'MoveTo', destination: target });
+    }
     
+    // 编辑时以原规则为基础，表单中没有的设置（优先级、继续匹配、生效时间、副本名称格式等）保持不变
     const rule = {
+        enabled: true,
+        priority: 0,
+        ...editingRule,
         id: appState.editingRuleId || `rule_${Date.now()}`,
         name,
-        enabled: true,
         logic: "and", // 所有条件必须同时满足
         conditions: conditions,
-        action: { type: 'MoveTo', destination: target },
-        priority: 0,
+        actions,
         conflict_strategy: conflictStrategy,
+        merge_file_strategy: mergeFileStrategy,
     };
     
     try {
@@ -4134,6 +4220,14 @@ async function saveRule() {
     }
 }
 
+// 冲突策略为合并时才显示合并时同名文件的策略
+function updateMergeFileStrategyVisibility() {
+    const group = document.getElementById('mergeFileStrategyGroup');
+    if (group) {
+        group.style.display = document.getElementById('conflictStrategy').value === 'merge' ? '' : 'none';
+    }
+}
+
 async function editRule(ruleId) {
     await openRuleModal(ruleId);
 }
@@ -4147,7 +4241,7 @@ async function deleteRule(ruleId) {
         type: 'rule',
         id: ruleId,
         name: rule.name,
-        destination: rule.action ? rule.action.destination : null
+        destination: getRuleDestination(rule) || null
     });
 }
 
@@ -4233,7 +4327,7 @@ async function executeDelete() {
             await loadFolders();
         } else if (item.type === 'group') {
             // 删除规则组（删除组内所有规则）
-            const rulesInGroup = appState.rules.filter(r => r.action.destination === item.destination);
+            const rulesInGroup = appState.rules.filter(r => getRuleDestination(r) === item.destination);
             let deletedCount = 0;
             for (const rule of rulesInGroup) {
                 try {
@@ -4828,7 +4922,7 @@ async function showBatchConfirm() {
     const filesPreviews = await Promise.all(
         appState.pendingBatch.map(async file => {
             try {
-                const preview = await invoke('preview_file_organization', { path: file.path });
+                const preview = await invoke('preview_file_organization', { path: file.path, folderId: findWatchFolderId(file.path) });
                 return {
                     ...file,
                     matched: preview.matched,
@@ -4952,6 +5046,9 @@ async function confirmBatch() {
     let skipCount = 0;
     let failCount = 0;
     
+    // 同一批次中冲突的处理方式可以记住
+    const batchId = `batch-${Date.now()}`;
+    
     // 逐个处理文件
     for (let i = 0; i < files.length; i++) {
         const file = files[i];
@@ -4979,7 +5076,8 @@ async function confirmBatch() {
                 for (const ruleId of appState.selectedRuleIds) {
                     result = await invoke('process_file_with_rule', { 
                         path: file.path,
-                        ruleId: ruleId
+                        ruleId: ruleId,
+                        batchId
                     });
                     if (result && result !== '文件未匹配任何规则') {
                         break; // 找到匹配的规则，停止尝试
@@ -4993,11 +5091,12 @@ async function confirmBatch() {
                 // 单个规则
                 result = await invoke('process_file_with_rule', { 
                     path: file.path,
-                    ruleId: appState.selectedRuleId
+                    ruleId: appState.selectedRuleId,
+                    batchId
                 });
             } else {
                 // 使用所有规则
-                result = await invoke('process_file', { path: file.path });
+                result = await invoke('process_file', { path: file.path, folderId: findWatchFolderId(file.path), batchId });
             }
             
             if (result === '') {
@@ -5007,6 +5106,13 @@ async function confirmBatch() {
                     statusEl.innerHTML = '<span style="color: #FF9800;" title="已处理过，跳过">⊘</span>';
                     fileItem?.classList.add('file-skipped');
                 }
+            } else if (result === '文件等待处理冲突') {
+                // 目标已存在，文件等待处理冲突，仍在原位置
+                skipCount++;
+                if (statusEl) {
+                    statusEl.innerHTML = '<span style="color: #FF9800;" title="等待处理冲突">⏸</span>';
+                    fileItem?.classList.add('file-skipped');
+                }
             } else if (result === '文件未匹配任何规则') {
                 // 文件/文件夹未匹配任何规则，也算跳过
                 skipCount++;
@@ -5839,7 +5945,7 @@ async function handleMiniFileDrop(files) {
             
             try {
                 // 根据当前选择的规则处理文件
-                const result = await invoke('process_file', { path: filePath });
+                const result = await invoke('process_file', { path: filePath, folderId: findWatchFolderId(filePath) });
                 
                 if (result) {
                     successCount++;
@@ -18,7 +18,7 @@ function renderRulesGrouped() {
     // 按目标文件夹分组
     const groups = new Map();
     appState.rules.forEach(rule => {
-        const dest = rule.action.destination || '';
+        const dest = getRuleDestination(rule) || '';
         if (!groups.has(dest)) {
             groups.set(dest, { rules: [], enabled: true });
         }
@@ -268,7 +268,7 @@ window.toggleGroupCollapse = function(destination) {
 
 // 切换组的启用/禁用状态
 window.toggleGroup = async function(destination) {
-    const rulesInGroup = appState.rules.filter(r => r.action.destination === destination);
+    const rulesInGroup = appState.rules.filter(r => getRuleDestination(r) === destination);
     const allEnabled = rulesInGroup.every(r => r.enabled);
     
     // 如果全部启用，则全部禁用；否则全部启用
@@ -291,7 +291,7 @@ window.toggleGroup = async function(destination) {
 
 // 删除整个组
 window.deleteGroup = function(destination, groupIndex) {
-    const rulesInGroup = appState.rules.filter(r => r.action.destination === destination);
+    const rulesInGroup = appState.rules.filter(r => getRuleDestination(r) === destination);
     
     // 显示删除确认模态框
     showDeleteConfirm({
@@ -362,7 +362,7 @@ window.moveGroupDown = async function(groupIndex) {
 
 // 在组内上移规则
 window.moveRuleUpInGroup = async function(ruleId, groupDestination) {
-    const rulesInGroup = appState.rules.filter(r => r.action.destination === groupDestination);
+    const rulesInGroup = appState.rules.filter(r => getRuleDestination(r) === groupDestination);
     const ruleIndexInGroup = rulesInGroup.findIndex(r => r.id === ruleId);
     
     if (ruleIndexInGroup <= 0) return;
@@ -384,7 +384,7 @@ window.moveRuleUpInGroup = async function(ruleId, groupDestination) {
 
 // 在组内下移规则
 window.moveRuleDownInGroup = async function(ruleId, groupDestination) {
-    const rulesInGroup = appState.rules.filter(r => r.action.destination === groupDestination);
+    const rulesInGroup = appState.rules.filter(r => getRuleDestination(r) === groupDestination);
     const ruleIndexInGroup = rulesInGroup.findIndex(r => r.id === ruleId);
     
     if (ruleIndexInGroup < 0 || ruleIndexInGroup >= rulesInGroup.length - 1) return;
@@ -408,7 +408,7 @@ window.moveRuleDownInGroup = async function(ruleId, groupDestination) {
 function getGroupedRules() {
     const groups = new Map();
     appState.rules.forEach(rule => {
-        const dest = rule.action.destination || '';
+        const dest = getRuleDestination(rule) || '';
         if (!groups.has(dest)) {
             groups.set(dest, { destination: dest, rules: [] });
         }